@@ -0,0 +1,33 @@
+//! Writes the detached signature for a release manifest.
+//!
+//! ```bash
+//! cargo run --example sign-manifest -- secrets/zipsign.priv manifest.json
+//! # -> manifest.json.sig
+//! ```
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 {
+        anyhow::bail!("usage: sign-manifest <zipsign.priv> <manifest.json>");
+    }
+    let (key_path, manifest_path) = (Path::new(&args[1]), Path::new(&args[2]));
+
+    let keys = zipsign_api::sign::read_signing_keys([fs::File::open(key_path)])?;
+    let prehash = zipsign_api::Prehash::calculate(&mut fs::File::open(manifest_path)?)?;
+
+    // The file name is the signing context, matching what the updater checks
+    let context = manifest_path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| anyhow::anyhow!("manifest path needs a UTF-8 file name"))?;
+    let signature = zipsign_api::sign::gather_signature_data(&keys, &prehash, Some(context.as_bytes()))?;
+
+    let sig_path = format!("{}.sig", manifest_path.display());
+    fs::write(&sig_path, signature)?;
+    println!("Wrote {}", sig_path);
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+// --- RELEASE CHANNELS ---
+
+/// Which pre-releases a machine accepts, based on the semver pre-release tag:
+///
+/// * `stable`: plain releases only (`v0.3.0`)
+/// * `beta`: also `-beta.N` and `-rc.N` (`v0.3.0-beta.1`)
+/// * `nightly`: any pre-release (`v0.3.0-nightly.20260301`, `-alpha.N`, ...)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    #[default]
+    Stable,
+    Beta,
+    Nightly,
+}
+
+impl Channel {
+    pub fn accepts(&self, version: &semver::Version) -> bool {
+        if version.pre.is_empty() {
+            return true;
+        }
+        let tag = version.pre.as_str();
+        match self {
+            Channel::Stable => false,
+            Channel::Beta => tag.starts_with("beta") || tag.starts_with("rc"),
+            Channel::Nightly => true,
+        }
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Channel::Stable => "stable",
+            Channel::Beta => "beta",
+            Channel::Nightly => "nightly",
+        })
+    }
+}
+
+impl FromStr for Channel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "stable" => Ok(Channel::Stable),
+            "beta" => Ok(Channel::Beta),
+            "nightly" => Ok(Channel::Nightly),
+            _ => anyhow::bail!("Unknown channel `{}` (expected stable, beta or nightly)", s),
+        }
+    }
+}
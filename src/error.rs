@@ -0,0 +1,124 @@
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// --- ERROR CLASSIFICATION (spec section 4) ---
+
+type Source = Box<dyn std::error::Error + Send + Sync>;
+
+/// Why an update failed. The messages of the variants the spec's error table
+/// reports on STDERR are its exact texts; the cause is kept as `source()`.
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateError {
+    /// No internet connection or the release host is unreachable. Silent.
+    #[error("No network connection")]
+    Network(#[source] Source),
+
+    /// The TLS handshake with the release host failed, e.g. an untrusted
+    /// certificate from an intercepting proxy. Not silent: it needs a CA bundle
+    /// or may be an attack.
+    #[error("Secure connection to the update server failed. Its certificate is not trusted.")]
+    Tls(#[source] Source),
+
+    /// The release API refused the request until `until` (Unix time). Silent.
+    #[error("API rate limit exceeded, resets in {}s", resets_in(*until))]
+    RateLimited { until: u64 },
+
+    /// The downloaded release is not signed by one of our keys.
+    #[error("Security Warning: Update signature verification failed. Update aborted.")]
+    Signature(#[source] Source),
+
+    /// The download broke off or did not match its published size/hash,
+    /// after all retries.
+    #[error("Update download failed.")]
+    Download(#[source] Source),
+
+    /// The new binary failed its health check and was rolled back.
+    #[error("Update failed validation. Restoring previous version.")]
+    HealthCheck { version: String },
+
+    /// The executable (or its directory) is not writable.
+    #[error("Insufficient permissions to update.")]
+    Permission(#[source] Source),
+
+    #[error("Update failed: {0:#}")]
+    Other(anyhow::Error),
+}
+
+impl UpdateError {
+    /// Failures the spec wants aborted without notifying the user (no
+    /// network, rate limit). Worth logging in a verbose mode only.
+    pub fn is_silent(&self) -> bool {
+        matches!(self, Self::Network(_) | Self::RateLimited { .. })
+    }
+
+    /// Download failures, except that an unreachable host counts as no network.
+    pub(crate) fn download(error: anyhow::Error) -> Self {
+        match Self::from(error) {
+            Self::Other(error) => Self::Download(error.into()),
+            classified => classified,
+        }
+    }
+}
+
+impl From<anyhow::Error> for UpdateError {
+    fn from(error: anyhow::Error) -> Self {
+        // Mirrors and backends may have wrapped the rate limit in context
+        let limit = error.chain().find_map(|cause| match cause.downcast_ref::<UpdateError>() {
+            Some(&UpdateError::RateLimited { until }) => Some(until),
+            _ => None,
+        });
+        if let Some(until) = limit {
+            return Self::RateLimited { until };
+        }
+        let error = match error.downcast::<UpdateError>() {
+            Ok(classified) => return classified,
+            Err(error) => error,
+        };
+        let offline = error
+            .chain()
+            .filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+            .any(|e| e.is_connect());
+        let tls = is_tls_failure(&error);
+        let denied = error
+            .chain()
+            .filter_map(|cause| cause.downcast_ref::<io::Error>())
+            .any(|e| e.kind() == io::ErrorKind::PermissionDenied);
+        if tls {
+            Self::Tls(error.into())
+        } else if offline {
+            Self::Network(error.into())
+        } else if denied {
+            Self::Permission(error.into())
+        } else {
+            Self::Other(error)
+        }
+    }
+}
+
+/// Whether the TLS handshake failed. reqwest reports that as a connect error;
+/// rustls' reason comes as an `InvalidData` I/O error wrapped in another one,
+/// which `source()` skips.
+pub(crate) fn is_tls_failure(error: &anyhow::Error) -> bool {
+    let connect = error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .any(|e| e.is_connect());
+    connect
+        && error
+            .chain()
+            .filter_map(|cause| cause.downcast_ref::<io::Error>())
+            .any(|mut e| loop {
+                if e.kind() == io::ErrorKind::InvalidData {
+                    break true;
+                }
+                match e.get_ref().and_then(|inner| inner.downcast_ref::<io::Error>()) {
+                    Some(inner) => e = inner,
+                    None => break false,
+                }
+            })
+}
+
+fn resets_in(until: u64) -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    until.saturating_sub(now)
+}
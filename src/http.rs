@@ -0,0 +1,232 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::{Certificate, NoProxy, Proxy, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::UpdateError;
+
+// --- SHARED HTTP CLIENT ---
+
+/// Proxy and TLS settings for every release source and download.
+///
+/// Without a `proxy`, the usual `HTTPS_PROXY`/`HTTP_PROXY`/`ALL_PROXY` and
+/// `NO_PROXY` environment variables apply. `ca_bundle` is a PEM file with
+/// extra root certificates, e.g. for an intercepting corporate proxy; the
+/// built-in roots stay trusted.
+///
+/// Pass it to the release sources (`with_network`) and to
+/// [`UpdaterBuilder::network`](crate::UpdaterBuilder::network).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    pub proxy: Option<String>,    // e.g. `http://proxy.example.com:3128`
+    pub no_proxy: Option<String>, // e.g. `localhost,.example.com`, defaults to `NO_PROXY`
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl NetworkConfig {
+    /// Checks that the proxy URL is valid and the CA bundle readable, so bad
+    /// settings can be refused before they are stored.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        client(self).map(drop)
+    }
+
+    /// Settings of `self`, falling back to `defaults` for those not set.
+    pub fn or(&self, defaults: &NetworkConfig) -> NetworkConfig {
+        NetworkConfig {
+            proxy: self.proxy.clone().or_else(|| defaults.proxy.clone()),
+            no_proxy: self.no_proxy.clone().or_else(|| defaults.no_proxy.clone()),
+            ca_bundle: self.ca_bundle.clone().or_else(|| defaults.ca_bundle.clone()),
+        }
+    }
+}
+
+/// HTTP client built from a [`NetworkConfig`] on first use, so that bad
+/// settings fail the update check rather than the creation of a source.
+pub(crate) struct LazyClient {
+    network: NetworkConfig,
+    client: OnceLock<Client>,
+}
+
+impl LazyClient {
+    pub(crate) fn new(network: NetworkConfig) -> Self {
+        Self {
+            network,
+            client: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn get(&self) -> anyhow::Result<&Client> {
+        if let Some(client) = self.client.get() {
+            return Ok(client);
+        }
+        let client = client(&self.network)?;
+        Ok(self.client.get_or_init(|| client))
+    }
+}
+
+fn client(network: &NetworkConfig) -> anyhow::Result<Client> {
+    let mut builder = Client::builder()
+        .user_agent(concat!("rs-example-self-update/", env!("CARGO_PKG_VERSION")))
+        .use_rustls_tls()
+        .connect_timeout(Duration::from_secs(10)); // Fail over quickly to the next mirror
+
+    if let Some(proxy) = &network.proxy {
+        let no_proxy = match &network.no_proxy {
+            Some(list) => NoProxy::from_string(list),
+            None => NoProxy::from_env(),
+        };
+        // `Proxy::all` takes almost anything, guessing `http://` for what is no URL.
+        // SOCKS would need reqwest's `socks` feature and fail on every request.
+        let url = Url::parse(proxy)
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some());
+        if url.is_none() {
+            anyhow::bail!("Invalid proxy {}, expected an HTTP(S) proxy like http://proxy.example.com:3128", proxy);
+        }
+        builder = builder.proxy(
+            Proxy::all(proxy)
+                .with_context(|| format!("Invalid proxy {}", proxy))?
+                .no_proxy(no_proxy),
+        );
+    }
+    if let Some(path) = &network.ca_bundle {
+        let pem = fs::read(path).with_context(|| format!("Cannot read CA bundle {}", path.display()))?;
+        let certs = Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("Invalid CA bundle {}", path.display()))?;
+        if certs.is_empty() {
+            anyhow::bail!("No certificates in CA bundle {}", path.display());
+        }
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    Ok(builder.build()?)
+}
+
+// --- RATE LIMITS ---
+
+/// `error_for_status` that reports a 403/429 carrying rate limit headers
+/// (`Retry-After`, or `X-RateLimit-Remaining: 0` with `X-RateLimit-Reset`)
+/// as [`UpdateError::RateLimited`].
+pub(crate) fn check(resp: Response) -> anyhow::Result<Response> {
+    if matches!(resp.status(), StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS) {
+        let header = |name: &str| resp.headers().get(name).and_then(|v| v.to_str().ok());
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let retry_after = header("retry-after").and_then(|s| s.parse::<u64>().ok()).map(|secs| now + secs);
+        let reset = match header("x-ratelimit-remaining") {
+            Some("0") => header("x-ratelimit-reset").and_then(|s| s.parse::<u64>().ok()),
+            _ => None,
+        };
+        if let Some(until) = retry_after.or(reset) {
+            return Err(UpdateError::RateLimited { until }.into());
+        }
+    }
+    Ok(resp.error_for_status()?)
+}
+
+/// Streams `url` into `dest`, failing on any non-success status.
+pub(crate) fn download_to(
+    client: &Client,
+    url: &str,
+    headers: HeaderMap,
+    dest: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let mut resp = check(client.get(url).headers(headers).send()?)?;
+    io::copy(&mut resp, dest)?;
+    Ok(())
+}
+
+/// Streams `url` from byte `offset` on into `dest`. Returns `false`, without
+/// writing anything, if the server will not serve that range.
+pub(crate) fn download_range(
+    client: &Client,
+    url: &str,
+    mut headers: HeaderMap,
+    offset: u64,
+    dest: &mut dyn Write,
+) -> anyhow::Result<bool> {
+    headers.insert(header::RANGE, HeaderValue::from_str(&format!("bytes={}-", offset))?);
+    let mut resp = client.get(url).headers(headers).send()?;
+    match resp.status() {
+        StatusCode::PARTIAL_CONTENT => {
+            io::copy(&mut resp, dest)?;
+            Ok(true)
+        }
+        // The partial file is longer than the asset, i.e. stale
+        StatusCode::RANGE_NOT_SATISFIABLE => Ok(false),
+        // 200 means `Range` is not supported
+        _ => check(resp).map(|_| false),
+    }
+}
+
+/// GETs a JSON array, following `Link: <...>; rel="next"` pagination
+/// (used by the GitHub, GitLab and Gitea APIs alike).
+pub(crate) fn get_json_pages<T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    headers: HeaderMap,
+) -> anyhow::Result<Vec<T>> {
+    Ok(get_json_pages_if_changed(client, url, headers, None)?
+        .map(|(items, _)| items)
+        .unwrap_or_default())
+}
+
+/// Like [`get_json_pages`], but sends `If-None-Match: <etag>` with the first
+/// page. Returns `None` if that page is unchanged (304), else the items and
+/// the first page's new ETag.
+pub(crate) fn get_json_pages_if_changed<T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    headers: HeaderMap,
+    etag: Option<&str>,
+) -> anyhow::Result<Option<(Vec<T>, Option<String>)>> {
+    let mut request = client.get(url).headers(headers.clone());
+    if let Some(etag) = etag {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+    let mut resp = request.send()?;
+    if resp.status() == StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    let new_etag = resp
+        .headers()
+        .get(header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+
+    let mut items = Vec::new();
+    loop {
+        let page = check(resp)?;
+        let next = page
+            .headers()
+            .get_all(header::LINK)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .find_map(next_link);
+        let page: Vec<T> = page.json()?;
+        items.extend(page);
+        match next {
+            Some(url) => resp = client.get(&url).headers(headers.clone()).send()?,
+            None => break,
+        }
+    }
+    Ok(Some((items, new_etag)))
+}
+
+fn next_link(link: &str) -> Option<String> {
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .contains("rel=\"next\"")
+            .then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_owned())
+    })
+}
@@ -0,0 +1,35 @@
+//! Background self-update for CLI applications distributed as signed
+//! release assets (GitHub by default, see [`source`] for other backends).
+//!
+//! ```no_run
+//! let public_key: [u8; 32] = *include_bytes!("../zipsign.pub");
+//! let updater = rs_example_self_update::Updater::configure()
+//!     .repo_owner("plops")
+//!     .repo_name("rs-example-self-update")
+//!     .current_version(env!("CARGO_PKG_VERSION"))
+//!     .verifying_keys(vec![public_key])
+//!     .build()?;
+//! let events = updater.spawn();
+//! # let _ = events;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+mod channel;
+mod error;
+mod http;
+mod patch;
+mod random;
+mod retry;
+pub mod source;
+mod state;
+mod throttle;
+mod updater;
+mod verify;
+
+pub use channel::Channel;
+pub use error::UpdateError;
+pub use http::NetworkConfig;
+pub use retry::{is_transient, RetryPolicy};
+pub use source::{Release, ReleaseAsset, ReleaseSource};
+pub use state::{IgnoredVersion, ReleaseCache, UpdateState};
+pub use updater::{default_target, UpdateEvent, Updater, UpdaterBuilder};
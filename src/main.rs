@@ -1,80 +1,455 @@
-use std::collections::HashSet;
-use std::env;
-use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
-use std::sync::mpsc::{channel, Sender, TryRecvError};
-use std::thread;
-use std::time::Duration;
+use std::process::ExitCode;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use anyhow::Context;
+use clap::{Args, Parser, Subcommand};
 use directories::ProjectDirs;
-use serde::{Deserialize, Serialize};
+use rs_example_self_update::source::{self, GitHub, Mirrors};
+use rs_example_self_update::{Channel, UpdateError, UpdateEvent, UpdateState, Updater};
+
+// --- COMMAND LINE ---
+
+const EXIT_CODES: &str = "\
+Exit codes:
+  0  Success (for `update check`: no update available)
+  1  Failure, e.g. update failed or health check failed
+  2  Invalid command line
+  3  `update check`: an update is available";
+
+/// Exit code of `update check` when there is an update
+const UPDATE_AVAILABLE: u8 = 3;
+
+/// Demo CLI that keeps itself up to date with signed releases.
+///
+/// Without a command it runs the demo app, checking for updates in the background.
+#[derive(Parser)]
+#[command(version, after_help = EXIT_CODES)]
+struct Cli {
+    /// Release source instead of GitHub, e.g. `gitlab:https://gitlab.example.com/group/project`,
+    /// a signed manifest URL or `file:///srv/releases`. Repeat for mirrors with failover.
+    #[arg(long = "source", value_name = "SPEC", global = true)]
+    sources: Vec<String>,
+
+    /// Also report failures that are silent by default (no network, rate limit)
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Check for updates even if the last check was less than a day ago
+    #[arg(long)]
+    check_now: bool,
+
+    // Health check flags of versions before the subcommands; their updaters
+    // still pass them to the new binary
+    #[arg(long, hide = true)]
+    health_check: bool,
+    #[arg(long, hide = true)]
+    simulate_failure: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check for, install or roll back updates
+    #[command(subcommand)]
+    Update(UpdateCommand),
+
+    /// Manage versions that are never installed automatically
+    #[command(subcommand)]
+    Ignored(IgnoredCommand),
+
+    /// Change the update settings of this machine; without options, show them
+    Config(ConfigArgs),
+
+    /// Verify that this binary works (run by the updater on a new version)
+    HealthCheck {
+        /// Fail on purpose, to test the rollback
+        #[arg(long)]
+        simulate_failure: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum UpdateCommand {
+    /// Look for a newer release without installing it
+    Check,
+
+    /// Install the newest release now, or a specific one
+    Apply {
+        /// Install exactly this version, e.g. 0.2.5 (downgrades ask first)
+        #[arg(long, value_name = "VERSION", value_parser = parse_version)]
+        to: Option<semver::Version>,
+
+        /// Do not ask before a downgrade
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Show the installed version, update settings and the last check
+    Status,
+
+    /// Go back to the version the last update replaced, and ignore this one
+    Rollback,
+}
+
+#[derive(Subcommand)]
+enum IgnoredCommand {
+    /// List the ignored versions, with health check failures (and their stderr with --verbose)
+    List,
+
+    /// Never install VERSION automatically
+    Add {
+        #[arg(value_parser = parse_version)]
+        version: semver::Version,
+
+        /// Why, e.g. "breaks the config file format"
+        #[arg(short, long)]
+        reason: Option<String>,
+    },
+
+    /// Allow VERSION again
+    Remove { version: String },
+
+    /// Allow all ignored versions again
+    Clear,
+}
+
+#[derive(Args)]
+struct ConfigArgs {
+    /// Release channel: stable, beta or nightly
+    #[arg(long)]
+    channel: Option<Channel>,
+
+    /// Only install versions matching REQ, e.g. ">=0.2, <0.3"
+    #[arg(long, value_name = "REQ", conflicts_with = "unpin")]
+    pin: Option<semver::VersionReq>,
 
-// --- ENUMS & STRUCTS ---
+    /// Remove the version pin
+    #[arg(long)]
+    unpin: bool,
 
-#[derive(Debug)]
-enum UpdateEvent {
-    Message(String),
-    Success(String), // Version
-    UpToDate,
-    Error(String),
+    /// Download bandwidth limit in bytes/s, e.g. 500K or 2M; `off` for none
+    #[arg(long, value_name = "RATE", value_parser = parse_rate)]
+    limit_rate: Option<u64>,
+
+    /// Proxy for all update requests, e.g. http://proxy:3128; `off` to use HTTPS_PROXY etc.
+    #[arg(long, value_name = "URL")]
+    proxy: Option<String>,
+
+    /// Hosts to reach without the proxy, e.g. localhost,.corp.example; `off` to remove
+    #[arg(long, value_name = "HOSTS")]
+    no_proxy: Option<String>,
+
+    /// PEM file with extra root certificates, e.g. of a corporate proxy; `off` to remove
+    #[arg(long, value_name = "PATH")]
+    ca_bundle: Option<String>,
 }
 
 // --- MAIN EXECUTION ---
 
-fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<ExitCode> {
+    let cli = Cli::parse();
+
     // 1. Health Check (Run by the updater to verify the new binary)
-    let args: Vec<String> = env::args().collect();
-    
-    if args.contains(&"--list-ignored".to_string()) {
-        let state = UpdateState::load();
-        println!("Ignored versions: {:?}", state.ignored_versions);
-        return Ok(());
+    match &cli.command {
+        Some(Command::HealthCheck { simulate_failure }) => return Ok(health_check(*simulate_failure)),
+        _ if cli.health_check || cli.simulate_failure => return Ok(health_check(cli.simulate_failure)),
+        _ => {}
     }
 
-    if args.contains(&"--simulate-failure".to_string()) {
+    // Embed public key (ensure zipsign.pub is in project root)
+    let public_key: [u8; 32] = *include_bytes!("../zipsign.pub");
+
+    // Proxy and CA bundle of this machine, for the release sources
+    let state_path = UpdateState::default_path("plops", "rs-example-self-update");
+    let network = UpdateState::load(&state_path).network;
+
+    let mut builder = Updater::configure();
+    builder
+        .repo_owner("plops")
+        .repo_name("rs-example-self-update")
+        .bin_name("rs-example-self-update") // Important: Matches binary name inside archive
+        .current_version(env!("CARGO_PKG_VERSION"))
+        .verifying_keys(vec![public_key])
+        .health_check_args(["--health-check", "--simulate-failure"]) // SIMULATE FAILURE FOR TESTING
+        .check_interval(Duration::from_secs(24 * 60 * 60))
+        .retry_failed_after(Duration::from_secs(7 * 24 * 60 * 60));
+
+    // Check even if the last check was less than a day ago; `update` commands always do
+    if cli.check_now || matches!(cli.command, Some(Command::Update(_))) {
+        builder.check_interval(Duration::ZERO);
+    }
+
+    // Alternative release sources, see `source::from_spec`
+    match cli.sources.as_slice() {
+        [] => {
+            // Private repositories: token from `GITHUB_TOKEN`, else from `github-token`
+            // in the config directory (e.g. ~/.config/rs-example-self-update/)
+            let mut github = GitHub::new("plops", "rs-example-self-update")?.with_network(&network);
+            if let Some(dirs) = ProjectDirs::from("com", "plops", "rs-example-self-update") {
+                github = github.with_token_file(dirs.config_dir().join("github-token"))?;
+            }
+            builder.source(github.with_env_token()?);
+        }
+        [spec] => {
+            builder.source(source::from_spec(spec, &[public_key], &network)?);
+        }
+        specs => {
+            let mut mirrors = Mirrors::new();
+            for spec in specs {
+                mirrors = mirrors.with(spec, source::from_spec(spec, &[public_key], &network)?);
+            }
+            builder.source(mirrors);
+        }
+    }
+    let updater = builder.build()?;
+
+    match cli.command {
+        Some(Command::Update(command)) => update(&updater, command),
+        Some(Command::Ignored(command)) => ignored(&updater, command, cli.verbose),
+        Some(Command::Config(args)) => config(&updater, args),
+        Some(Command::HealthCheck { .. }) => unreachable!("handled above"),
+        None => run_app(updater, cli.verbose),
+    }
+}
+
+fn health_check(simulate_failure: bool) -> ExitCode {
+    if simulate_failure {
         println!("SIMULATED FAILURE: Exiting with error.");
-        std::process::exit(1);
+        return ExitCode::FAILURE;
+    }
+    // In a real app, check config integrity or basic startup logic here
+    println!("Health check passed!");
+    ExitCode::SUCCESS
+}
+
+fn update(updater: &Updater, command: UpdateCommand) -> anyhow::Result<ExitCode> {
+    match command {
+        UpdateCommand::Check => {
+            let (tx, rx) = std::sync::mpsc::channel();
+            let printer = print_events(rx);
+            let result = updater.check(tx);
+            let _ = printer.join();
+            match result {
+                Ok(Some(release)) => {
+                    println!("v{} available (installed: v{}).", release.version, env!("CARGO_PKG_VERSION"));
+                    Ok(ExitCode::from(UPDATE_AVAILABLE))
+                }
+                Ok(None) => Ok(ExitCode::SUCCESS),
+                Err(e) => Ok(failed(&e)),
+            }
+        }
+
+        UpdateCommand::Apply { to, yes } => {
+            // A specific version; downgrades ask first
+            if let Some(target) = &to {
+                let current = semver::Version::parse(env!("CARGO_PKG_VERSION"))?;
+                if *target < current && !yes {
+                    print!("Downgrade from v{} to v{}? [y/N] ", current, target);
+                    std::io::Write::flush(&mut std::io::stdout())?;
+                    let mut answer = String::new();
+                    std::io::stdin().read_line(&mut answer)?;
+                    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+                        println!("Aborted.");
+                        return Ok(ExitCode::FAILURE);
+                    }
+                }
+            }
+
+            let (tx, rx) = std::sync::mpsc::channel();
+            let printer = print_events(rx);
+            let result = match &to {
+                Some(version) => updater.install_version(&version.to_string(), tx),
+                None => updater.run(tx),
+            };
+            let _ = printer.join();
+            match result {
+                Ok(()) => Ok(ExitCode::SUCCESS),
+                Err(e) => Ok(failed(&e)),
+            }
+        }
+
+        UpdateCommand::Status => {
+            let state = updater.load_state();
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            println!("Version:        v{}", env!("CARGO_PKG_VERSION"));
+            print_settings(updater, &state);
+            match state.last_check {
+                Some(at) => println!("Last check:     {} ago", duration(now.saturating_sub(at))),
+                None => println!("Last check:     never"),
+            }
+            if let Some(until) = state.rate_limited_until.filter(|&until| now < until) {
+                let source = state.rate_limited_source.as_deref().unwrap_or("-");
+                println!("Rate limited:   {} for {}", source, duration(until - now));
+            }
+            if let Some(mirror) = &state.last_mirror {
+                println!("Last mirror:    {}", mirror);
+            }
+            let ignored = state.ignored_versions.values().filter(|v| v.is_active()).count();
+            println!("Ignored:        {} version(s)", ignored);
+            let rollback = updater.backup_path()?.exists();
+            println!("Rollback:       {}", if rollback { "available" } else { "-" });
+            Ok(ExitCode::SUCCESS)
+        }
+
+        UpdateCommand::Rollback => match updater.rollback() {
+            Ok(()) => {
+                println!("Rolled back. v{} is now ignored.", env!("CARGO_PKG_VERSION"));
+                Ok(ExitCode::SUCCESS)
+            }
+            Err(e) => Ok(failed(&e)),
+        },
+    }
+}
+
+fn ignored(updater: &Updater, command: IgnoredCommand, verbose: bool) -> anyhow::Result<ExitCode> {
+    let mut state = updater.load_state();
+    match command {
+        IgnoredCommand::List => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            for (version, ignored) in &state.ignored_versions {
+                let mut line = format!("{:<16}{}", version, ignored.reason.as_deref().unwrap_or("-"));
+                if let Some(last) = ignored.last_failure {
+                    let plural = if ignored.failures == 1 { "" } else { "s" };
+                    line += &format!(" ({} failure{}, last {} ago", ignored.failures, plural, duration(now.saturating_sub(last)));
+                    if let Some(code) = ignored.exit_code {
+                        line += &format!(", exit code {}", code);
+                    }
+                    line += ")";
+                }
+                match ignored.until {
+                    Some(until) if now < until => line += &format!(", retried in {}", duration(until - now)),
+                    Some(_) => line += ", expired",
+                    None => {}
+                }
+                println!("{}", line);
+                if verbose {
+                    for stderr in ignored.stderr.iter().flat_map(|s| s.lines()) {
+                        println!("    | {}", stderr);
+                    }
+                }
+            }
+        }
+        IgnoredCommand::Add { version, reason } => {
+            println!("Ignoring v{}.", version);
+            state.ignore(version.to_string(), reason)?;
+        }
+        IgnoredCommand::Remove { version } => {
+            let version = version.trim_start_matches('v');
+            if !state.unignore(version)? {
+                eprintln!("v{} is not ignored.", version);
+                return Ok(ExitCode::FAILURE);
+            }
+            println!("v{} is no longer ignored.", version);
+        }
+        IgnoredCommand::Clear => {
+            state.clear_ignored()?;
+            println!("No versions are ignored anymore.");
+        }
     }
+    Ok(ExitCode::SUCCESS)
+}
 
-    if args.contains(&"--test-blacklist".to_string()) {
-        let mut state = UpdateState::load();
-        state.mark_bad("9.9.9".to_string())?;
-        println!("Marked 9.9.9 as bad.");
-        return Ok(());
+fn config(updater: &Updater, args: ConfigArgs) -> anyhow::Result<ExitCode> {
+    let mut state = updater.load_state();
+    if let Some(channel) = args.channel {
+        state.channel = Some(channel);
+    }
+    if let Some(req) = args.pin {
+        state.version_req = Some(req);
+    }
+    if args.unpin {
+        state.version_req = None;
+    }
+    if let Some(limit) = args.limit_rate {
+        state.download_limit = Some(limit);
     }
+    // `off` removes a network setting
+    let setting = |value: Option<String>| value.map(|v| (v != "off").then_some(v));
+    if let Some(proxy) = setting(args.proxy) {
+        state.network.proxy = proxy;
+    }
+    if let Some(no_proxy) = setting(args.no_proxy) {
+        state.network.no_proxy = no_proxy;
+    }
+    if let Some(ca_bundle) = setting(args.ca_bundle) {
+        // Stored for later runs from any working directory
+        state.network.ca_bundle = ca_bundle.map(std::path::absolute).transpose()?;
+    }
+    // A setting the update check cannot use is not saved
+    state.network.validate().context("Invalid network settings, not saved")?;
+    state.save()?;
+    print_settings(updater, &state);
+    Ok(ExitCode::SUCCESS)
+}
 
-    if args.contains(&"--health-check".to_string()) {
-        // In a real app, check config integrity or basic startup logic here
-        println!("Health check passed!");
-        return Ok(());
+fn print_settings(updater: &Updater, state: &UpdateState) {
+    println!("Channel:        {}", updater.channel(state));
+    match &state.version_req {
+        Some(req) => println!("Pinned to:      {}", req),
+        None => println!("Pinned to:      -"),
     }
+    match updater.download_limit(state) {
+        Some(limit) => println!("Download limit: {} bytes/s", limit),
+        None => println!("Download limit: -"),
+    }
+    let network = &state.network;
+    println!("Proxy:          {}", network.proxy.as_deref().unwrap_or("- (environment)"));
+    println!("No proxy:       {}", network.no_proxy.as_deref().unwrap_or("-"));
+    match &network.ca_bundle {
+        Some(path) => println!("CA bundle:      {}", path.display()),
+        None => println!("CA bundle:      -"),
+    }
+}
 
+fn run_app(updater: Updater, verbose: bool) -> ! {
     println!("App Version: {}", env!("CARGO_PKG_VERSION"));
-    
+
+    // The update thread returns right away if the last check was recent
+    let mut update_status = match updater.time_until_next_check(&updater.load_state()) {
+        Some(Duration::MAX) => "No automatic update checks (--check-now to check now)".to_string(),
+        Some(wait) => format!("Next update check in {}h (--check-now to check now)", wait.as_secs().div_ceil(3600)),
+        None => "Checking for updates in background...".to_string(),
+    };
+
     // 2. Spawn the Update Thread
-    let (tx, rx) = channel();
-    thread::spawn(move || {
-        if let Err(e) = run_background_update(tx.clone()) {
-            let _ = tx.send(UpdateEvent::Error(e.to_string()));
-        }
-    });
+    let rx = updater.spawn();
 
     // 3. Main Application Loop (The "Animation")
-    let spinner = vec!['|', '/', '-', '\\'];
+    let spinner = ['|', '/', '-', '\\'];
     let mut idx = 0;
-    let mut update_status = "Checking for updates in background...".to_string();
 
     // We run for a limited time for the demo, or until the user interrupts.
     // In a real app, this would be your main task.
     loop {
+        use std::io::Write;
+
         // A. Process Update Events (Non-blocking)
         match rx.try_recv() {
             Ok(event) => match event {
                 UpdateEvent::Message(msg) => update_status = msg,
                 UpdateEvent::UpToDate => update_status = "System is up to date.".to_string(),
+                UpdateEvent::Held(v) => update_status = format!("v{} available but held by version pin", v),
+                UpdateEvent::Staged(v, percent) => {
+                    update_status = format!("v{} is rolling out ({}% of machines), not here yet", v, percent)
+                }
+                UpdateEvent::Retrying(attempt, delay, e) => {
+                    update_status = format!("{}; attempt {} in {:.1}s", e, attempt, delay.as_secs_f64())
+                }
                 UpdateEvent::Success(v) => update_status = format!("Update ready! Restart to use v{}", v),
-                UpdateEvent::Error(e) => update_status = format!("Update failed: {}", e),
+                UpdateEvent::Error(e) => {
+                    print!("\r{:<80}\r", ""); // Keep the message off the spinner line
+                    let _ = std::io::stdout().flush();
+                    report(&e, verbose);
+                    update_status = if e.is_silent() {
+                        "Update check skipped.".to_string()
+                    } else {
+                        e.to_string()
+                    };
+                }
             },
             Err(TryRecvError::Empty) => {} // No message
             Err(TryRecvError::Disconnected) => {
@@ -86,7 +461,6 @@ fn main() -> anyhow::Result<()> {
         // B. Render UI
         // Clear line and print status with padding to clear old messages
         print!("\r[{}] Application Running... Status: {:<50}", spinner[idx], update_status);
-        use std::io::Write;
         std::io::stdout().flush().unwrap();
 
         // C. Update Animation State
@@ -99,151 +473,81 @@ fn main() -> anyhow::Result<()> {
     }
 }
 
-// --- UPDATE LOGIC (Runs in Background) ---
-
-fn run_background_update(tx: Sender<UpdateEvent>) -> anyhow::Result<()> {
-    let current_exe = env::current_exe()?;
-    let backup_path = current_exe.with_extension("bak");
-    let mut state = UpdateState::load();
-
-    // 1. Configure Updater
-    let os = std::env::consts::OS;
-    let arch = match std::env::consts::ARCH {
-        "x86_64" => "amd64",
-        "aarch64" => "arm64",
-        _ => std::env::consts::ARCH,
-    };
-    let target = format!("{}-{}", os, arch);
-    
-    // Embed public key (ensure zipsign.pub is in project root)
-    let public_key: [u8; 32] = *include_bytes!("../zipsign.pub");
-
-    let mut builder = self_update::backends::github::Update::configure();
-    builder
-        .repo_owner("plops")
-        .repo_name("rs-example-self-update")
-        .bin_name("rs-example-self-update") // Important: Matches binary name inside archive
-        .target(&target)
-        .current_version(env!("CARGO_PKG_VERSION"))
-        .verifying_keys(vec![public_key]);
-
-    // 2. Check for Release (Peek)
-    tx.send(UpdateEvent::Message("Querying GitHub...".into()))?;
-    let release = builder.build()?.get_latest_release()?;
-
-    // 3. Blacklist Check
-    if state.is_bad(&release.version) {
-        tx.send(UpdateEvent::Message(format!("Skipping bad version {}", release.version)))?;
-        return Ok(());
-    }
-
-    if !self_update::version::bump_is_greater(env!("CARGO_PKG_VERSION"), &release.version)? {
-        tx.send(UpdateEvent::UpToDate)?;
-        return Ok(());
-    }
-
-    // 4. Update Sequence
-    tx.send(UpdateEvent::Message(format!("Downloading v{}...", release.version)))?;
-
-    // Create Backup
-    fs::copy(&current_exe, &backup_path)?;
-
-    // Perform Update (Swap binary on disk)
-    // Note: On Windows, self_update renames the running file to allow writing the new one.
-    // The running process continues in memory fine.
-    match builder.build()?.update() {
-        Ok(status) => {
-            if !status.updated() {
-                tx.send(UpdateEvent::UpToDate)?;
-                return Ok(());
-            }
-
-            let new_version = status.version().to_string();
-            tx.send(UpdateEvent::Message("Verifying new binary health...".into()))?;
-
-            // 5. Health Check
-            let output = Command::new(&current_exe)
-                .arg("--simulate-failure") // SIMULATE FAILURE FOR TESTING
-                .output();
-
-            match output {
-                Ok(o) if o.status.success() => {
-                    // Success! Clean backup
-                    let _ = fs::remove_file(&backup_path);
-                    tx.send(UpdateEvent::Success(new_version))?;
+// Prints the events of a foreground update until its sender is dropped
+fn print_events(rx: Receiver<UpdateEvent>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        for event in rx {
+            match event {
+                UpdateEvent::Message(msg) => println!("{}", msg),
+                UpdateEvent::UpToDate => println!("Up to date (v{}).", env!("CARGO_PKG_VERSION")),
+                UpdateEvent::Held(v) => println!("v{} available but held by version pin.", v),
+                UpdateEvent::Staged(v, percent) => {
+                    println!("v{} is rolling out ({}% of machines), not here yet.", v, percent)
                 }
-                _ => {
-                    // Fail! Rollback
-                    tx.send(UpdateEvent::Message("Health check failed. Rolling back...".into()))?;
-                    
-                    // Mark bad
-                    state.mark_bad(new_version.clone())?;
-                    
-                    // Restore backup
-                    // On Windows, we overwrite the "new" broken file with the backup
-                    fs::rename(&backup_path, &current_exe)?;
-                    tx.send(UpdateEvent::Error(format!("Version {} broken. Rolled back.", new_version)))?;
+                UpdateEvent::Retrying(attempt, delay, e) => {
+                    println!("{}; attempt {} in {:.1}s", e, attempt, delay.as_secs_f64())
                 }
+                UpdateEvent::Success(v) => println!("Installed v{}. Restart to use it.", v),
+                UpdateEvent::Error(e) => report(&e, true),
             }
         }
-        Err(e) => {
-            // Network/Signature error - restore backup just in case
-            if backup_path.exists() {
-                let _ = fs::rename(&backup_path, &current_exe);
-            }
-            return Err(e.into());
-        }
-    }
-
-    Ok(())
+    })
 }
 
-// --- PERSISTENT STATE (The Blacklist) ---
-
-#[derive(Serialize, Deserialize, Default)]
-struct UpdateState {
-    ignored_versions: HashSet<String>,
+// Commands run in the foreground were asked for, so even silent failures are shown
+fn failed(error: &UpdateError) -> ExitCode {
+    report(error, true);
+    ExitCode::FAILURE
 }
 
-impl UpdateState {
-    fn load() -> Self {
-        let path = Self::get_path();
-        if path.exists() {
-            if let Ok(file) = std::fs::File::open(path) {
-                if let Ok(state) = serde_json::from_reader(file) {
-                    return state;
-                }
-            }
-        }
-        Self::default()
+// Spec section 4: the exact STDERR texts, nothing for network and rate limit
+// failures unless `--verbose`, which also shows the underlying cause
+fn report(error: &UpdateError, verbose: bool) {
+    if error.is_silent() && !verbose {
+        return;
     }
-
-    fn save(&self) -> anyhow::Result<()> {
-        let path = Self::get_path();
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
+    eprintln!("{}", error);
+    if verbose {
+        let mut cause = std::error::Error::source(error);
+        while let Some(e) = cause {
+            eprintln!("  caused by: {}", e);
+            cause = e.source();
         }
-        let file = fs::File::create(path)?;
-        serde_json::to_writer_pretty(file, self)?;
-        Ok(())
     }
+}
 
-    fn mark_bad(&mut self, version: String) -> anyhow::Result<()> {
-        self.ignored_versions.insert(version);
-        self.save()
+// `6d 23h`, `3h 5m`, `12m` or `40s`
+fn duration(secs: u64) -> String {
+    match (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60) {
+        (0, 0, 0) => format!("{}s", secs),
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
     }
+}
 
-    fn is_bad(&self, version: &str) -> bool {
-        let v_clean = version.trim_start_matches('v');
-        self.ignored_versions.contains(v_clean) || self.ignored_versions.contains(version)
-    }
+// `update apply --to` and `ignored add`, with or without a leading `v`
+fn parse_version(version: &str) -> Result<semver::Version, semver::Error> {
+    semver::Version::parse(version.strip_prefix('v').unwrap_or(version))
+}
 
-    fn get_path() -> PathBuf {
-        // Cross-platform cache location
-        if let Some(proj) = ProjectDirs::from("com", "plops", "rs-example-self-update") {
-            proj.cache_dir().join("state.json")
-        } else {
-            PathBuf::from("update_state.json")
-        }
+// `500K`, `2M` or plain bytes per second; `off` or `0` for no limit
+fn parse_rate(rate: &str) -> anyhow::Result<u64> {
+    if rate == "off" {
+        return Ok(0);
     }
-}
\ No newline at end of file
+    let upper = rate.to_ascii_uppercase();
+    let (digits, unit) = if let Some(digits) = upper.strip_suffix('K') {
+        (digits, 1024)
+    } else if let Some(digits) = upper.strip_suffix('M') {
+        (digits, 1024 * 1024)
+    } else {
+        (upper.as_str(), 1)
+    };
+    let bytes = digits
+        .parse::<u64>()
+        .map_err(|_| anyhow::anyhow!("invalid rate {}, expected e.g. 500K or 2M", rate))?;
+    bytes
+        .checked_mul(unit)
+        .ok_or_else(|| anyhow::anyhow!("rate {} is too large", rate))
+}
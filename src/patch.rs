@@ -0,0 +1,16 @@
+use std::fs;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+// --- BINARY DELTA PATCHES (zstd --patch-from) ---
+
+/// Rebuilds the new binary at `out` from the `old` one and a zstd frame made
+/// with `zstd --long=31 --patch-from=<old> <new>`.
+pub(crate) fn apply(old: &Path, patch: &Path, out: &Path) -> anyhow::Result<()> {
+    let old = fs::read(old)?;
+    let patch = BufReader::new(fs::File::open(patch)?);
+    let mut decoder = zstd::stream::read::Decoder::with_ref_prefix(patch, &old)?;
+    decoder.window_log_max(31)?; // The window has to span the whole old binary
+    io::copy(&mut decoder, &mut fs::File::create(out)?)?;
+    Ok(())
+}
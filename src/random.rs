@@ -0,0 +1,11 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+// --- RANDOMNESS ---
+
+/// Non-cryptographic random number, good enough for retry jitter and rollout
+/// buckets. `RandomState` is randomly seeded per process and gives each new
+/// instance different keys, so the hash of nothing differs from call to call.
+pub(crate) fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
@@ -0,0 +1,125 @@
+use std::io;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::error::is_tls_failure;
+use crate::random::random_u64;
+
+// --- RETRY POLICY ---
+
+/// How often, and after which errors, release lookups and downloads are
+/// retried. The default retries once after about a second (spec section 4:
+/// "Retry once, then abort") and only for [`is_transient`] errors.
+///
+/// The delay doubles after every failed attempt; `jitter` (0.0-1.0) shortens
+/// each delay by a random fraction of up to that much, so many machines that
+/// failed together do not retry in lockstep.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    attempts: u32,
+    base_delay: Duration,
+    jitter: f64,
+    retryable: Arc<dyn Fn(&anyhow::Error) -> bool + Send + Sync>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 2,
+            base_delay: Duration::from_secs(1),
+            jitter: 0.5,
+            retryable: Arc::new(is_transient),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A policy that gives up after the first failure.
+    pub fn never() -> Self {
+        Self::default().with_attempts(1)
+    }
+
+    /// Total number of attempts, including the first one.
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry.
+    pub fn with_base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Decides which errors are worth another attempt. Defaults to [`is_transient`].
+    pub fn with_retryable(mut self, retryable: impl Fn(&anyhow::Error) -> bool + Send + Sync + 'static) -> Self {
+        self.retryable = Arc::new(retryable);
+        self
+    }
+
+    /// Runs `op` until it succeeds, fails with an error that is not retryable,
+    /// or runs out of attempts. `on_retry` is told the number of the next
+    /// attempt, the delay before it and the error that caused it.
+    pub(crate) fn run<T>(
+        &self,
+        mut op: impl FnMut() -> anyhow::Result<T>,
+        mut on_retry: impl FnMut(u32, Duration, &anyhow::Error) -> anyhow::Result<()>,
+    ) -> anyhow::Result<T> {
+        let mut attempt = 1;
+        loop {
+            match op() {
+                Err(e) if attempt < self.attempts && (self.retryable)(&e) => {
+                    attempt += 1;
+                    let delay = self.delay(attempt - 1);
+                    on_retry(attempt, delay, &e)?;
+                    thread::sleep(delay);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // Exponential backoff for the `retry`th retry (1-based), minus jitter
+    fn delay(&self, retry: u32) -> Duration {
+        let backoff = self.base_delay.saturating_mul(1 << (retry - 1).min(16));
+        let random = (random_u64() % 1000) as f64 / 1000.0;
+        backoff.mul_f64(1.0 - self.jitter * random)
+    }
+}
+
+/// Errors that may go away by themselves: connection failures, timeouts,
+/// interrupted transfers, HTTP 5xx and 429.
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    // A rejected certificate stays rejected
+    if is_tls_failure(error) {
+        return false;
+    }
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            let status = e.status();
+            let server_side = status.is_some_and(|s| s.is_server_error() || s.as_u16() == 429);
+            return e.is_connect() || e.is_timeout() || e.is_body() || e.is_request() || server_side;
+        }
+        if let Some(e) = cause.downcast_ref::<io::Error>() {
+            return matches!(
+                e.kind(),
+                io::ErrorKind::TimedOut
+                    | io::ErrorKind::Interrupted
+                    | io::ErrorKind::UnexpectedEof
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+            );
+        }
+        false
+    })
+}
@@ -0,0 +1,97 @@
+use std::io::Write;
+
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+
+use super::{Release, ReleaseAsset, ReleaseSource};
+use crate::http::{self, LazyClient, NetworkConfig};
+
+// --- GITEA / FORGEJO RELEASES API ---
+
+#[derive(Deserialize)]
+struct GtRelease {
+    tag_name: String,
+    body: Option<String>, // Release notes, may carry a `Rollout: 10%` line
+    draft: bool,
+    assets: Vec<GtAsset>,
+}
+
+#[derive(Deserialize)]
+struct GtAsset {
+    name: String,
+    browser_download_url: String,
+    size: u64,
+}
+
+impl From<GtRelease> for Release {
+    fn from(r: GtRelease) -> Self {
+        Release {
+            version: r.tag_name.trim_start_matches('v').to_owned(),
+            assets: r
+                .assets
+                .into_iter()
+                .map(|a| ReleaseAsset {
+                    name: a.name,
+                    download_url: a.browser_download_url,
+                    size: Some(a.size),
+                    ..Default::default()
+                })
+                .collect(),
+            rollout: r.body.as_deref().and_then(super::rollout_from_notes),
+        }
+    }
+}
+
+/// Releases of a Gitea or Forgejo repository (e.g. Codeberg or a self-hosted
+/// instance). Both share the same `/api/v1` releases API.
+pub struct Gitea {
+    base_url: String,
+    repo_owner: String,
+    repo_name: String,
+    client: LazyClient,
+}
+
+impl Gitea {
+    /// `base_url` is the instance root, e.g. `https://codeberg.org`.
+    pub fn new(base_url: &str, repo_owner: &str, repo_name: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            repo_owner: repo_owner.to_owned(),
+            repo_name: repo_name.to_owned(),
+            client: LazyClient::new(NetworkConfig::default()),
+        })
+    }
+
+    /// Proxy and CA bundle for the requests to this source.
+    pub fn with_network(mut self, network: &NetworkConfig) -> Self {
+        self.client = LazyClient::new(network.clone());
+        self
+    }
+}
+
+impl ReleaseSource for Gitea {
+    fn name(&self) -> &str {
+        "Gitea"
+    }
+
+    fn list_releases(&self) -> anyhow::Result<Vec<Release>> {
+        let url = format!(
+            "{}/api/v1/repos/{}/{}/releases?limit=50",
+            self.base_url, self.repo_owner, self.repo_name
+        );
+        let releases: Vec<GtRelease> = http::get_json_pages(self.client.get()?, &url, HeaderMap::new())?;
+        Ok(releases
+            .into_iter()
+            .filter(|r| !r.draft)
+            .map(Release::from)
+            .collect())
+    }
+
+    fn download_asset(&self, asset: &ReleaseAsset, dest: &mut dyn Write) -> anyhow::Result<()> {
+        http::download_to(self.client.get()?, &asset.download_url, HeaderMap::new(), dest)
+    }
+
+    fn resume_asset(&self, asset: &ReleaseAsset, offset: u64, dest: &mut dyn Write) -> anyhow::Result<bool> {
+        http::download_range(self.client.get()?, &asset.download_url, HeaderMap::new(), offset, dest)
+    }
+}
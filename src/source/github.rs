@@ -0,0 +1,196 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use serde::Deserialize;
+
+use super::{Release, ReleaseAsset, ReleaseSource};
+use crate::http::{self, LazyClient, NetworkConfig};
+
+// --- GITHUB RELEASES API ---
+
+#[derive(Deserialize)]
+struct GhRelease {
+    tag_name: String,
+    body: Option<String>, // Release notes, may carry a `Rollout: 10%` line
+    #[serde(default)]
+    draft: bool, // Listed only for tokens with push access
+    assets: Vec<GhAsset>,
+}
+
+#[derive(Deserialize)]
+struct GhAsset {
+    name: String,
+    url: String, // API url, serves the binary with `Accept: application/octet-stream`
+    size: u64,
+}
+
+impl From<GhRelease> for Release {
+    fn from(r: GhRelease) -> Self {
+        Release {
+            version: r.tag_name.trim_start_matches('v').to_owned(),
+            assets: r
+                .assets
+                .into_iter()
+                .map(|a| ReleaseAsset {
+                    name: a.name,
+                    download_url: a.url,
+                    target: None,
+                    size: Some(a.size),
+                    sha256: None,
+                })
+                .collect(),
+            rollout: r.body.as_deref().and_then(super::rollout_from_notes),
+        }
+    }
+}
+
+/// Releases of `https://github.com/<owner>/<repo>`.
+///
+/// Private repositories need a token with read access to the repository's
+/// contents, see [`GitHub::with_token`]. It is sent with API and asset
+/// requests and never printed.
+pub struct GitHub {
+    api_url: String,
+    repo_owner: String,
+    repo_name: String,
+    token: Option<HeaderValue>,
+    client: LazyClient,
+}
+
+impl GitHub {
+    pub fn new(repo_owner: &str, repo_name: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            api_url: "https://api.github.com".to_owned(),
+            repo_owner: repo_owner.to_owned(),
+            repo_name: repo_name.to_owned(),
+            token: None,
+            client: LazyClient::new(NetworkConfig::default()),
+        })
+    }
+
+    /// Proxy and CA bundle for the requests to this source.
+    pub fn with_network(mut self, network: &NetworkConfig) -> Self {
+        self.client = LazyClient::new(network.clone());
+        self
+    }
+
+    /// Override the API root, e.g. for GitHub Enterprise (`https://ghe.example.com/api/v3`).
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Personal access token or fine-grained token for private repositories.
+    pub fn with_token(mut self, token: &str) -> anyhow::Result<Self> {
+        let mut value = HeaderValue::from_str(&format!("Bearer {}", token.trim()))
+            .map_err(|_| anyhow::anyhow!("GitHub token contains invalid characters"))?;
+        value.set_sensitive(true); // Kept out of Debug output
+        self.token = Some(value);
+        Ok(self)
+    }
+
+    /// Token from `GITHUB_TOKEN` or `GH_TOKEN`, if set.
+    pub fn with_env_token(self) -> anyhow::Result<Self> {
+        match env::var("GITHUB_TOKEN").or_else(|_| env::var("GH_TOKEN")) {
+            Ok(token) if !token.trim().is_empty() => self.with_token(&token),
+            _ => Ok(self),
+        }
+    }
+
+    /// Token from the first line of a file, if it exists. Keep the file
+    /// readable only by the user.
+    pub fn with_token_file<P: AsRef<Path>>(self, path: P) -> anyhow::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => match content.lines().next() {
+                Some(token) if !token.trim().is_empty() => self.with_token(token),
+                _ => Ok(self),
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(self),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn repo_url(&self) -> String {
+        format!("{}/repos/{}/{}", self.api_url, self.repo_owner, self.repo_name)
+    }
+
+    fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(token) = &self.token {
+            headers.insert(header::AUTHORIZATION, token.clone());
+        }
+        headers
+    }
+
+    fn asset_headers(&self) -> HeaderMap {
+        let mut headers = self.headers();
+        headers.insert(header::ACCEPT, HeaderValue::from_static("application/octet-stream"));
+        headers
+    }
+
+    // GitHub answers 404 rather than 403 for private repositories it will not show
+    fn auth_error(&self, error: anyhow::Error) -> anyhow::Error {
+        let status = error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<reqwest::Error>()?.status());
+        let hint = match (status.map(|s| s.as_u16()), &self.token) {
+            (Some(401), _) => "the token is invalid or expired",
+            (Some(404), None) => "private repositories need a token (GITHUB_TOKEN)",
+            (Some(404), Some(_)) => "the token has no access to this repository",
+            _ => return error,
+        };
+        error.context(format!(
+            "GitHub authentication failed for {}/{}: {}",
+            self.repo_owner, self.repo_name, hint
+        ))
+    }
+}
+
+impl ReleaseSource for GitHub {
+    fn name(&self) -> &str {
+        "GitHub"
+    }
+
+    fn list_releases(&self) -> anyhow::Result<Vec<Release>> {
+        let url = format!("{}/releases?per_page=100", self.repo_url());
+        let releases: Vec<GhRelease> =
+            http::get_json_pages(self.client.get()?, &url, self.headers()).map_err(|e| self.auth_error(e))?;
+        Ok(published(releases))
+    }
+
+    // Newest releases come first, so an unchanged first page means nothing new.
+    // A 304 does not count against the API rate limit.
+    fn list_releases_if_changed(&self, etag: Option<&str>) -> anyhow::Result<Option<(Vec<Release>, Option<String>)>> {
+        let url = format!("{}/releases?per_page=100", self.repo_url());
+        let listing = http::get_json_pages_if_changed::<GhRelease>(self.client.get()?, &url, self.headers(), etag)
+            .map_err(|e| self.auth_error(e))?;
+        Ok(listing.map(|(releases, etag)| (published(releases), etag)))
+    }
+
+    fn latest_release(&self) -> anyhow::Result<Release> {
+        let url = format!("{}/releases/latest", self.repo_url());
+        let get = || -> anyhow::Result<GhRelease> {
+            Ok(http::check(self.client.get()?.get(&url).headers(self.headers()).send()?)?.json()?)
+        };
+        Ok(get().map_err(|e| self.auth_error(e))?.into())
+    }
+
+    // The API asset URL redirects to storage on another host; the client drops
+    // the token on that redirect.
+    fn download_asset(&self, asset: &ReleaseAsset, dest: &mut dyn Write) -> anyhow::Result<()> {
+        http::download_to(self.client.get()?, &asset.download_url, self.asset_headers(), dest).map_err(|e| self.auth_error(e))
+    }
+
+    fn resume_asset(&self, asset: &ReleaseAsset, offset: u64, dest: &mut dyn Write) -> anyhow::Result<bool> {
+        http::download_range(self.client.get()?, &asset.download_url, self.asset_headers(), offset, dest)
+            .map_err(|e| self.auth_error(e))
+    }
+}
+
+/// Drops drafts, which are not meant to be installed yet.
+fn published(releases: Vec<GhRelease>) -> Vec<Release> {
+    releases.into_iter().filter(|r| !r.draft).map(Release::from).collect()
+}
@@ -0,0 +1,101 @@
+use std::io::Write;
+
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+
+use super::{Release, ReleaseAsset, ReleaseSource};
+use crate::http::{self, LazyClient, NetworkConfig};
+
+// --- GITLAB RELEASES API ---
+
+#[derive(Deserialize)]
+struct GlRelease {
+    tag_name: String,
+    description: Option<String>, // Release notes, may carry a `Rollout: 10%` line
+    assets: GlAssets,
+}
+
+#[derive(Deserialize)]
+struct GlAssets {
+    links: Vec<GlLink>,
+}
+
+#[derive(Deserialize)]
+struct GlLink {
+    name: String,
+    url: String,
+    direct_asset_url: Option<String>,
+}
+
+impl From<GlRelease> for Release {
+    fn from(r: GlRelease) -> Self {
+        Release {
+            version: r.tag_name.trim_start_matches('v').to_owned(),
+            assets: r
+                .assets
+                .links
+                .into_iter()
+                .map(|l| ReleaseAsset {
+                    name: l.name,
+                    download_url: l.direct_asset_url.unwrap_or(l.url),
+                    ..Default::default()
+                })
+                .collect(),
+            rollout: r.description.as_deref().and_then(super::rollout_from_notes),
+        }
+    }
+}
+
+/// Releases of a GitLab project, on gitlab.com or a self-hosted instance.
+/// Asset links must be named like the GitHub assets (`<bin>-<os>-<arch>.tar.gz`).
+pub struct GitLab {
+    base_url: String,
+    project: String, // `group/subgroup/project`
+    client: LazyClient,
+}
+
+impl GitLab {
+    pub fn new(project: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            base_url: "https://gitlab.com".to_owned(),
+            project: project.trim_matches('/').to_owned(),
+            client: LazyClient::new(NetworkConfig::default()),
+        })
+    }
+
+    /// Proxy and CA bundle for the requests to this source.
+    pub fn with_network(mut self, network: &NetworkConfig) -> Self {
+        self.client = LazyClient::new(network.clone());
+        self
+    }
+
+    /// Self-hosted instance, e.g. `https://gitlab.example.com`.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_owned();
+        self
+    }
+}
+
+impl ReleaseSource for GitLab {
+    fn name(&self) -> &str {
+        "GitLab"
+    }
+
+    fn list_releases(&self) -> anyhow::Result<Vec<Release>> {
+        let url = format!(
+            "{}/api/v4/projects/{}/releases?per_page=100",
+            self.base_url,
+            self.project.replace('/', "%2F")
+        );
+        let releases: Vec<GlRelease> = http::get_json_pages(self.client.get()?, &url, HeaderMap::new())?;
+        Ok(releases.into_iter().map(Release::from).collect())
+    }
+
+    fn download_asset(&self, asset: &ReleaseAsset, dest: &mut dyn Write) -> anyhow::Result<()> {
+        http::download_to(self.client.get()?, &asset.download_url, HeaderMap::new(), dest)
+    }
+
+    fn resume_asset(&self, asset: &ReleaseAsset, offset: u64, dest: &mut dyn Write) -> anyhow::Result<bool> {
+        http::download_range(self.client.get()?, &asset.download_url, HeaderMap::new(), offset, dest)
+    }
+}
@@ -0,0 +1,92 @@
+use std::fs;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use super::{Release, ReleaseAsset, ReleaseSource};
+
+// --- LOCAL DIRECTORY RELEASES ---
+
+/// Releases laid out on disk like GitHub release assets, one folder per version:
+///
+/// ```text
+/// releases/
+///   v0.2.7/rs-example-self-update-linux-amd64.tar.gz
+///   v0.2.8/rs-example-self-update-linux-amd64.tar.gz
+/// ```
+///
+/// Archives must still carry a valid zipsign signature.
+pub struct LocalDir {
+    root: PathBuf,
+}
+
+impl LocalDir {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Accepts `file:///srv/releases` as well as a plain path.
+    pub fn from_url(url: &str) -> anyhow::Result<Self> {
+        if !url.starts_with("file:") {
+            return Ok(Self::new(url));
+        }
+        let root = reqwest::Url::parse(url)?
+            .to_file_path()
+            .map_err(|_| anyhow::anyhow!("Not a local path: {}", url))?;
+        Ok(Self::new(root))
+    }
+}
+
+impl ReleaseSource for LocalDir {
+    fn name(&self) -> &str {
+        "local directory"
+    }
+
+    fn list_releases(&self) -> anyhow::Result<Vec<Release>> {
+        let mut releases = Vec::new();
+        for entry in fs::read_dir(&self.root)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let Some(folder) = entry.file_name().to_str().map(str::to_owned) else {
+                continue;
+            };
+
+            let mut assets = Vec::new();
+            for file in fs::read_dir(entry.path())? {
+                let file = file?;
+                if let Some(name) = file.file_name().to_str() {
+                    assets.push(ReleaseAsset {
+                        name: name.to_owned(),
+                        download_url: file.path().to_string_lossy().into_owned(),
+                        ..Default::default()
+                    });
+                }
+            }
+            releases.push(Release {
+                version: folder.trim_start_matches('v').to_owned(),
+                assets,
+                rollout: None,
+            });
+        }
+        Ok(releases)
+    }
+
+    fn download_asset(&self, asset: &ReleaseAsset, dest: &mut dyn Write) -> anyhow::Result<()> {
+        let mut file = fs::File::open(&asset.download_url)?;
+        io::copy(&mut file, dest)?;
+        Ok(())
+    }
+
+    fn resume_asset(&self, asset: &ReleaseAsset, offset: u64, dest: &mut dyn Write) -> anyhow::Result<bool> {
+        let mut file = fs::File::open(&asset.download_url)?;
+        if offset > file.metadata()?.len() {
+            return Ok(false);
+        }
+        file.seek(SeekFrom::Start(offset))?;
+        io::copy(&mut file, dest)?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+use zipsign_api::PUBLIC_KEY_LENGTH;
+
+use super::{Release, ReleaseAsset, ReleaseSource};
+use crate::error::UpdateError;
+use crate::http::{self, LazyClient, NetworkConfig};
+use crate::verify;
+
+// --- STATIC RELEASE MANIFEST ---
+
+// manifest.json, signed by `manifest.json.sig` next to it:
+//
+// {
+//   "releases": [
+//     {
+//       "version": "0.2.8",
+//       "rollout": 10,
+//       "assets": {
+//         "linux-amd64": {
+//           "url": "https://releases.example.com/v0.2.8/rs-example-self-update-linux-amd64.tar.gz",
+//           "size": 1234567,
+//           "sha256": "9f86d081...",
+//           "patches": [
+//             {
+//               "url": "https://releases.example.com/v0.2.8/rs-example-self-update-linux-amd64-from-0.2.7.patch.tar.gz",
+//               "size": 23456,
+//               "sha256": "2c26b46b..."
+//             }
+//           ]
+//         }
+//       }
+//     }
+//   ]
+// }
+
+#[derive(Deserialize)]
+struct ManifestFile {
+    releases: Vec<ManifestRelease>,
+}
+
+#[derive(Deserialize)]
+struct ManifestRelease {
+    version: String,
+    rollout: Option<u8>, // Percent of machines, omit for a full release
+    assets: BTreeMap<String, ManifestAsset>, // Keyed by target, e.g. `linux-amd64`
+}
+
+#[derive(Deserialize)]
+struct ManifestAsset {
+    url: String,
+    size: u64,
+    sha256: String,
+    #[serde(default)]
+    patches: Vec<ManifestAsset>, // Delta patches to this release, from older versions
+}
+
+impl ManifestAsset {
+    fn into_assets(self, target: String) -> Vec<ReleaseAsset> {
+        let mut assets: Vec<_> = self
+            .patches
+            .into_iter()
+            .flat_map(|p| p.into_assets(target.clone()))
+            .collect();
+        assets.push(ReleaseAsset {
+            name: self.url.rsplit('/').next().unwrap_or_default().to_owned(),
+            download_url: self.url,
+            target: Some(target),
+            size: Some(self.size),
+            sha256: Some(self.sha256),
+        });
+        assets
+    }
+}
+
+/// Releases listed in a signed `manifest.json` served by any HTTP server.
+///
+/// The manifest is rejected unless `<url>.sig` holds a zipsign signature from
+/// one of `verifying_keys`. Assets are picked by their target key; the archives
+/// themselves are still signature-checked by the updater against their file name.
+pub struct Manifest {
+    url: String,
+    verifying_keys: Vec<[u8; PUBLIC_KEY_LENGTH]>,
+    client: LazyClient,
+}
+
+impl Manifest {
+    pub fn new(
+        url: &str,
+        verifying_keys: impl Into<Vec<[u8; PUBLIC_KEY_LENGTH]>>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            url: url.to_owned(),
+            verifying_keys: verifying_keys.into(),
+            client: LazyClient::new(NetworkConfig::default()),
+        })
+    }
+
+    /// Proxy and CA bundle for the requests to this source.
+    pub fn with_network(mut self, network: &NetworkConfig) -> Self {
+        self.client = LazyClient::new(network.clone());
+        self
+    }
+
+    fn fetch(&self, url: &str) -> anyhow::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        http::download_to(self.client.get()?, url, HeaderMap::new(), &mut buf)?;
+        Ok(buf)
+    }
+}
+
+impl ReleaseSource for Manifest {
+    fn name(&self) -> &str {
+        "release manifest"
+    }
+
+    fn list_releases(&self) -> anyhow::Result<Vec<Release>> {
+        let data = self.fetch(&self.url)?;
+        let signature = self.fetch(&format!("{}.sig", self.url))?;
+        let context = self.url.rsplit('/').next().unwrap_or_default();
+        verify::verify_detached(&data, &signature, context, &self.verifying_keys)
+            .map_err(|e| UpdateError::Signature(e.context("Manifest signature verification failed").into()))?;
+
+        let manifest: ManifestFile = serde_json::from_slice(&data)?;
+        Ok(manifest
+            .releases
+            .into_iter()
+            .map(|r| Release {
+                version: r.version.trim_start_matches('v').to_owned(),
+                assets: r
+                    .assets
+                    .into_iter()
+                    .flat_map(|(target, a)| a.into_assets(target))
+                    .collect(),
+                rollout: r.rollout.map(|p| p.min(100)),
+            })
+            .collect())
+    }
+
+    fn download_asset(&self, asset: &ReleaseAsset, dest: &mut dyn Write) -> anyhow::Result<()> {
+        http::download_to(self.client.get()?, &asset.download_url, HeaderMap::new(), dest)
+    }
+
+    fn resume_asset(&self, asset: &ReleaseAsset, offset: u64, dest: &mut dyn Write) -> anyhow::Result<bool> {
+        http::download_range(self.client.get()?, &asset.download_url, HeaderMap::new(), offset, dest)
+    }
+}
@@ -0,0 +1,111 @@
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use super::{Release, ReleaseAsset, ReleaseSource};
+use crate::error::UpdateError;
+
+// --- MIRROR FAILOVER ---
+
+/// An ordered list of sources tried one after another. A mirror is skipped on
+/// connection errors, timeouts, HTTP 403/429 (including rate limits) or an
+/// unreachable local directory; any other error is reported as is.
+///
+/// Assets are downloaded from the mirror that served the release metadata.
+#[derive(Default)]
+pub struct Mirrors {
+    mirrors: Vec<(String, Box<dyn ReleaseSource>)>,
+    active: Mutex<Option<usize>>,
+}
+
+impl Mirrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a mirror; `label` identifies it in messages and in `state.json`.
+    pub fn with(mut self, label: &str, source: impl ReleaseSource + 'static) -> Self {
+        self.mirrors.push((label.to_owned(), Box::new(source)));
+        self
+    }
+
+    fn first_available<T>(
+        &self,
+        query: impl Fn(&dyn ReleaseSource) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let mut last_error = None;
+        for (idx, (label, source)) in self.mirrors.iter().enumerate() {
+            match query(source.as_ref()) {
+                Ok(value) => {
+                    *self.active.lock().unwrap() = Some(idx);
+                    return Ok(value);
+                }
+                Err(e) if is_unavailable(&e) => {
+                    last_error = Some(e.context(format!("mirror {} unavailable", label)));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No mirrors configured")))
+    }
+}
+
+impl ReleaseSource for Mirrors {
+    fn name(&self) -> &str {
+        "mirrors"
+    }
+
+    fn list_releases(&self) -> anyhow::Result<Vec<Release>> {
+        self.first_available(|source| source.list_releases())
+    }
+
+    fn list_releases_if_changed(&self, etag: Option<&str>) -> anyhow::Result<Option<(Vec<Release>, Option<String>)>> {
+        self.first_available(|source| source.list_releases_if_changed(etag))
+    }
+
+    fn latest_release(&self) -> anyhow::Result<Release> {
+        self.first_available(|source| source.latest_release())
+    }
+
+    fn download_asset(&self, asset: &ReleaseAsset, dest: &mut dyn Write) -> anyhow::Result<()> {
+        let idx = self
+            .active
+            .lock()
+            .unwrap()
+            .ok_or_else(|| anyhow::anyhow!("No mirror has served a release yet"))?;
+        self.mirrors[idx].1.download_asset(asset, dest)
+    }
+
+    fn resume_asset(&self, asset: &ReleaseAsset, offset: u64, dest: &mut dyn Write) -> anyhow::Result<bool> {
+        let idx = self
+            .active
+            .lock()
+            .unwrap()
+            .ok_or_else(|| anyhow::anyhow!("No mirror has served a release yet"))?;
+        self.mirrors[idx].1.resume_asset(asset, offset, dest)
+    }
+
+    fn served_by(&self) -> Option<String> {
+        let idx = (*self.active.lock().unwrap())?;
+        Some(self.mirrors[idx].0.clone())
+    }
+}
+
+// Errors worth trying the next mirror for
+fn is_unavailable(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(UpdateError::RateLimited { .. }) = cause.downcast_ref::<UpdateError>() {
+            return true;
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            let throttled = matches!(e.status().map(|s| s.as_u16()), Some(403) | Some(429));
+            return e.is_connect() || e.is_timeout() || throttled;
+        }
+        if let Some(e) = cause.downcast_ref::<io::Error>() {
+            return matches!(
+                e.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied | io::ErrorKind::TimedOut
+            );
+        }
+        false
+    })
+}
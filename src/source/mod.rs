@@ -0,0 +1,244 @@
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+use zipsign_api::PUBLIC_KEY_LENGTH;
+
+use crate::http::NetworkConfig;
+
+mod gitea;
+mod github;
+mod gitlab;
+mod local;
+mod manifest;
+mod mirrors;
+mod s3;
+
+pub use gitea::Gitea;
+pub use github::GitHub;
+pub use gitlab::GitLab;
+pub use local::LocalDir;
+pub use manifest::Manifest;
+pub use mirrors::Mirrors;
+pub use s3::S3;
+
+// --- RELEASE METADATA ---
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub download_url: String,
+    pub target: Option<String>, // Set by backends that key assets by target
+    pub size: Option<u64>,      // Checked after download when the backend publishes it
+    pub sha256: Option<String>, // Hex encoded
+}
+
+impl ReleaseAsset {
+    fn is_for(&self, target: &str) -> bool {
+        match &self.target {
+            Some(t) => t == target,
+            None => self.name.contains(target),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Release {
+    pub version: String, // Without the `v` prefix
+    pub assets: Vec<ReleaseAsset>,
+    pub rollout: Option<u8>, // Percentage of machines offered this release, `None` for all
+}
+
+impl Release {
+    /// First full archive for `target` (e.g. `linux-amd64`), either declared
+    /// by the backend or found in the asset name.
+    pub fn asset_for(&self, target: &str) -> Option<&ReleaseAsset> {
+        self.assets
+            .iter()
+            .find(|asset| asset.is_for(target) && !asset.name.contains(".patch."))
+    }
+
+    /// Delta patch archive from `from_version` to this release, named
+    /// `<bin>-<target>-from-<version>.patch.tar.gz` (or `.zip`).
+    pub fn patch_for(&self, target: &str, from_version: &str) -> Option<&ReleaseAsset> {
+        let suffix = format!("-from-{}.patch.", from_version.trim_start_matches('v'));
+        self.assets
+            .iter()
+            .find(|asset| asset.is_for(target) && asset.name.contains(&suffix))
+    }
+
+    /// Whether a machine in rollout `bucket` (0-99) is offered this release.
+    pub fn covers(&self, bucket: u8) -> bool {
+        self.rollout.is_none_or(|percent| bucket < percent)
+    }
+}
+
+// --- BACKEND INTERFACE ---
+
+/// Where releases come from. The updater only talks to this trait, so the
+/// blacklist, backup, signature and health checks apply to every backend.
+pub trait ReleaseSource: Send + Sync {
+    /// Short name used in progress messages, e.g. `GitHub`.
+    fn name(&self) -> &str;
+
+    fn list_releases(&self) -> anyhow::Result<Vec<Release>>;
+
+    /// Conditional [`ReleaseSource::list_releases`]: `None` if nothing changed
+    /// since the listing that returned `etag`, else the releases and their new
+    /// ETag. The default always lists, without an ETag.
+    fn list_releases_if_changed(&self, _etag: Option<&str>) -> anyhow::Result<Option<(Vec<Release>, Option<String>)>> {
+        Ok(Some((self.list_releases()?, None)))
+    }
+
+    /// Defaults to the highest semver in [`ReleaseSource::list_releases`].
+    fn latest_release(&self) -> anyhow::Result<Release> {
+        latest(self.list_releases()?)
+            .ok_or_else(|| anyhow::anyhow!("No releases found on {}", self.name()))
+    }
+
+    /// Writes the raw (still signed) asset to `dest`.
+    fn download_asset(&self, asset: &ReleaseAsset, dest: &mut dyn Write) -> anyhow::Result<()>;
+
+    /// Writes the asset from byte `offset` on, to resume an interrupted
+    /// download. Returns `false`, without writing, if that is not possible.
+    fn resume_asset(&self, _asset: &ReleaseAsset, _offset: u64, _dest: &mut dyn Write) -> anyhow::Result<bool> {
+        Ok(false)
+    }
+
+    /// Which mirror answered the last query, for sources that fail over.
+    fn served_by(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<S: ReleaseSource + ?Sized> ReleaseSource for Box<S> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn list_releases(&self) -> anyhow::Result<Vec<Release>> {
+        (**self).list_releases()
+    }
+
+    fn list_releases_if_changed(&self, etag: Option<&str>) -> anyhow::Result<Option<(Vec<Release>, Option<String>)>> {
+        (**self).list_releases_if_changed(etag)
+    }
+
+    fn latest_release(&self) -> anyhow::Result<Release> {
+        (**self).latest_release()
+    }
+
+    fn download_asset(&self, asset: &ReleaseAsset, dest: &mut dyn Write) -> anyhow::Result<()> {
+        (**self).download_asset(asset, dest)
+    }
+
+    fn resume_asset(&self, asset: &ReleaseAsset, offset: u64, dest: &mut dyn Write) -> anyhow::Result<bool> {
+        (**self).resume_asset(asset, offset, dest)
+    }
+
+    fn served_by(&self) -> Option<String> {
+        (**self).served_by()
+    }
+}
+
+/// Builds a source from a one-line spec, as used on the command line:
+///
+/// * `github:<owner>/<repo>` (token from `GITHUB_TOKEN`, if set)
+/// * `gitlab:<group>/<project>` or `gitlab:https://gitlab.example.com/<group>/<project>`
+/// * `gitea:https://codeberg.org/<owner>/<repo>` (Gitea and Forgejo)
+/// * `s3://<bucket>/<prefix>?endpoint=http://127.0.0.1:9000&region=us-east-1`
+///   (credentials from `AWS_ACCESS_KEY_ID`/`AWS_SECRET_ACCESS_KEY`, if set)
+/// * `https://releases.example.com/manifest.json` (signed manifest)
+/// * `file:///srv/releases` or a plain directory path
+///
+/// `verifying_keys` are needed for sources that sign their metadata, `network`
+/// applies to all but local directories.
+pub fn from_spec(
+    spec: &str,
+    verifying_keys: &[[u8; PUBLIC_KEY_LENGTH]],
+    network: &NetworkConfig,
+) -> anyhow::Result<Box<dyn ReleaseSource>> {
+    if let Some(repo) = spec.strip_prefix("github:") {
+        let (owner, name) = split_repo(repo)?;
+        return Ok(Box::new(GitHub::new(owner, name)?.with_network(network).with_env_token()?));
+    }
+    if let Some(project) = spec.strip_prefix("gitlab:") {
+        let (base_url, project) = split_instance(project)?;
+        let mut source = GitLab::new(&project)?.with_network(network);
+        if let Some(base_url) = base_url {
+            source = source.with_base_url(&base_url);
+        }
+        return Ok(Box::new(source));
+    }
+    if let Some(repo) = spec.strip_prefix("gitea:") {
+        let (base_url, repo) = split_instance(repo)?;
+        let base_url = base_url.ok_or_else(|| anyhow::anyhow!("gitea source needs the instance URL: {}", spec))?;
+        let (owner, name) = split_repo(&repo)?;
+        return Ok(Box::new(Gitea::new(&base_url, owner, name)?.with_network(network)));
+    }
+    if spec.starts_with("s3://") {
+        let url = reqwest::Url::parse(spec)?;
+        let bucket = url.host_str().ok_or_else(|| anyhow::anyhow!("s3 source needs a bucket: {}", spec))?;
+        let param = |name: &str| url.query_pairs().find(|(k, _)| k == name).map(|(_, v)| v.into_owned());
+        let region = param("region").unwrap_or_else(|| "us-east-1".to_owned());
+        let endpoint = param("endpoint").unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        let source = S3::new(&endpoint, bucket)?
+            .with_prefix(url.path())
+            .with_region(&region)
+            .with_env_credentials()
+            .with_network(network);
+        return Ok(Box::new(source));
+    }
+    if spec.starts_with("http://") || spec.starts_with("https://") {
+        return Ok(Box::new(Manifest::new(spec, verifying_keys)?.with_network(network)));
+    }
+    Ok(Box::new(LocalDir::from_url(spec)?))
+}
+
+// `owner/repo`
+fn split_repo(repo: &str) -> anyhow::Result<(&str, &str)> {
+    repo.trim_matches('/')
+        .split_once('/')
+        .ok_or_else(|| anyhow::anyhow!("expected <owner>/<repo>, got {}", repo))
+}
+
+// `https://host/path` -> (Some("https://host"), "path"), `path` -> (None, "path")
+fn split_instance(spec: &str) -> anyhow::Result<(Option<String>, String)> {
+    if !spec.starts_with("http://") && !spec.starts_with("https://") {
+        return Ok((None, spec.trim_matches('/').to_owned()));
+    }
+    let url = reqwest::Url::parse(spec)?;
+    Ok((
+        Some(url.origin().ascii_serialization()),
+        url.path().trim_matches('/').to_owned(),
+    ))
+}
+
+/// Staged rollout percentage from release notes, given as a line such as
+/// `Rollout: 10%`. Used by the forge backends, which have no metadata field for it.
+pub(crate) fn rollout_from_notes(notes: &str) -> Option<u8> {
+    notes.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        if !key.trim().eq_ignore_ascii_case("rollout") {
+            return None;
+        }
+        value.trim().trim_end_matches('%').trim().parse::<u8>().ok().map(|p| p.min(100))
+    })
+}
+
+/// Highest release by semver; versions that do not parse are ignored.
+pub fn latest(releases: Vec<Release>) -> Option<Release> {
+    latest_where(releases, |_| true)
+}
+
+/// Highest release by semver among those whose version satisfies `filter`.
+pub fn latest_where(
+    releases: Vec<Release>,
+    filter: impl Fn(&semver::Version) -> bool,
+) -> Option<Release> {
+    releases
+        .into_iter()
+        .filter_map(|r| semver::Version::parse(&r.version).ok().map(|v| (v, r)))
+        .filter(|(v, _)| filter(v))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, r)| r)
+}
@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use directories::ProjectDirs;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::channel::Channel;
+use crate::http::NetworkConfig;
+use crate::random::random_u64;
+use crate::source::Release;
+
+// --- PERSISTENT STATE (The Blacklist) ---
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct UpdateState {
+    #[serde(deserialize_with = "ignored_versions")]
+    pub ignored_versions: BTreeMap<String, IgnoredVersion>, // Never installed automatically
+    pub last_mirror: Option<String>, // Mirror that served the last installed update
+    pub channel: Option<Channel>,    // Chosen by the user, overrides the app's default
+    pub version_req: Option<semver::VersionReq>, // Pin, e.g. `>=0.2, <0.3`
+    pub rollout_bucket: Option<u8>, // 0-99, drawn once per machine for staged rollouts
+    pub download_limit: Option<u64>, // Bytes per second, overrides the app's default
+    pub network: NetworkConfig,      // Proxy and CA bundle for this machine
+    pub release_cache: Option<ReleaseCache>, // Last release listing, reused while unchanged
+    pub rate_limited_until: Option<u64>,     // Unix time; no checks before the API limit resets
+    pub rate_limited_source: Option<String>, // Name of the release source the limit applies to
+    pub last_check: Option<u64>,             // Unix time of the last successful release lookup
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+/// Why a version is in [`UpdateState::ignored_versions`], and the health
+/// check failures that put it there.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IgnoredVersion {
+    pub reason: Option<String>, // Free text; none for entries from before reasons were kept
+    pub until: Option<u64>,     // Unix time after which the version is retried; never if none
+    pub failures: u32,          // Failed health checks
+    pub first_failure: Option<u64>, // Unix time
+    pub last_failure: Option<u64>,  // Unix time
+    pub exit_code: Option<i32>, // Of the last failed health check; none if it was killed or did not start
+    pub stderr: Option<String>, // End of the last failed health check's stderr
+}
+
+impl IgnoredVersion {
+    /// Whether the entry still keeps the version from being installed.
+    pub fn is_active(&self) -> bool {
+        self.until.is_none_or(|until| unix_now() < until)
+    }
+}
+
+/// Bytes of health check stderr kept per version
+const STDERR_LIMIT: usize = 4096;
+
+/// Release listing as returned with `etag`, for conditional requests.
+#[derive(Serialize, Deserialize)]
+pub struct ReleaseCache {
+    pub etag: String,
+    pub releases: Vec<Release>,
+}
+
+impl UpdateState {
+    /// Loads the state from `path`, falling back to an empty state if the file
+    /// is missing or unreadable.
+    pub fn load(path: &Path) -> Self {
+        if path.exists() {
+            if let Ok(file) = fs::File::open(path) {
+                if let Ok(state) = serde_json::from_reader::<_, Self>(file) {
+                    return Self {
+                        path: path.to_path_buf(),
+                        ..state
+                    };
+                }
+            }
+        }
+        Self {
+            path: path.to_path_buf(),
+            ..Self::default()
+        }
+    }
+
+    /// Writes the state to a temporary file next to it and renames that into
+    /// place, so that another process never loads a half-written file.
+    pub fn save(&self) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // One temporary file per save, so concurrent saves do not mix
+        let tmp_path = self.path.with_extension(format!("json.{:016x}.tmp", random_u64()));
+        let write = || -> anyhow::Result<()> {
+            let mut file = fs::File::create(&tmp_path)?;
+            serde_json::to_writer_pretty(&mut file, self)?;
+            file.sync_all()?;
+            Ok(())
+        };
+        if let Err(e) = write().and_then(|()| Ok(fs::rename(&tmp_path, &self.path)?)) {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Records a failed health check of `version` and ignores it until
+    /// `retry_after` has passed, or for good if `None`.
+    pub fn mark_bad(
+        &mut self,
+        version: String,
+        exit_code: Option<i32>,
+        stderr: &str,
+        retry_after: Option<Duration>,
+    ) -> anyhow::Result<()> {
+        let now = unix_now();
+        let entry = self.ignored_versions.entry(version.trim_start_matches('v').to_owned()).or_default();
+        entry.reason = Some("Health check failed".to_owned());
+        entry.until = retry_after.map(|after| now.saturating_add(after.as_secs()));
+        entry.failures += 1;
+        entry.first_failure.get_or_insert(now);
+        entry.last_failure = Some(now);
+        entry.exit_code = exit_code;
+
+        // The end is where the error usually is
+        let mut start = stderr.len().saturating_sub(STDERR_LIMIT);
+        while !stderr.is_char_boundary(start) {
+            start += 1;
+        }
+        entry.stderr = Some(stderr[start..].to_owned());
+        self.save()
+    }
+
+    /// Never installs `version` automatically (explicit installs still can).
+    /// Failures recorded earlier are kept.
+    pub fn ignore(&mut self, version: String, reason: Option<String>) -> anyhow::Result<()> {
+        let entry = self.ignored_versions.entry(version.trim_start_matches('v').to_owned()).or_default();
+        entry.reason = reason;
+        entry.until = None;
+        self.save()
+    }
+
+    /// Allows `version` again. Returns whether it was ignored.
+    pub fn unignore(&mut self, version: &str) -> anyhow::Result<bool> {
+        let v_clean = version.trim_start_matches('v');
+        let removed = self.ignored_versions.remove(v_clean).is_some() | self.ignored_versions.remove(version).is_some();
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Allows all ignored versions again.
+    pub fn clear_ignored(&mut self) -> anyhow::Result<()> {
+        self.ignored_versions.clear();
+        self.save()
+    }
+
+    /// Whether `version` is ignored. Expired entries are not.
+    pub fn is_bad(&self, version: &str) -> bool {
+        let v_clean = version.trim_start_matches('v');
+        let entry = self.ignored_versions.get(v_clean).or_else(|| self.ignored_versions.get(version));
+        entry.is_some_and(IgnoredVersion::is_active)
+    }
+
+    /// This machine's staged rollout bucket (0-99). Drawn at random on first
+    /// use and persisted, so a machine keeps its place as a rollout widens.
+    pub fn rollout_bucket(&mut self) -> anyhow::Result<u8> {
+        if let Some(bucket) = self.rollout_bucket {
+            return Ok(bucket);
+        }
+        let bucket = (random_u64() % 100) as u8;
+        self.rollout_bucket = Some(bucket);
+        self.save()?;
+        Ok(bucket)
+    }
+
+    /// Default location of `state.json` for an application, inside its
+    /// cross-platform cache directory.
+    pub fn default_path(organization: &str, application: &str) -> PathBuf {
+        if let Some(proj) = ProjectDirs::from("com", organization, application) {
+            proj.cache_dir().join("state.json")
+        } else {
+            PathBuf::from("update_state.json")
+        }
+    }
+}
+
+// `ignored_versions` used to be a plain list of versions
+fn ignored_versions<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<String, IgnoredVersion>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Map(BTreeMap<String, IgnoredVersion>),
+        List(Vec<String>),
+    }
+    Ok(match Stored::deserialize(deserializer)? {
+        Stored::Map(map) => map,
+        Stored::List(list) => list.into_iter().map(|v| (v, IgnoredVersion::default())).collect(),
+    })
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_legacy_ignored_list() {
+        let state: UpdateState = serde_json::from_str(r#"{"ignored_versions":["9.9.9"]}"#).unwrap();
+        let entry = &state.ignored_versions["9.9.9"];
+        assert_eq!(entry.until, None);
+        assert!(state.is_bad("9.9.9"));
+        assert!(state.is_bad("v9.9.9"));
+        assert!(!state.is_bad("9.9.8"));
+    }
+
+    #[test]
+    fn expired_entries_are_not_bad() {
+        let state: UpdateState =
+            serde_json::from_str(r#"{"ignored_versions":{"9.9.9":{"reason":"Health check failed","until":1}}}"#)
+                .unwrap();
+        assert!(!state.is_bad("v9.9.9"));
+    }
+
+    #[test]
+    fn long_retry_period_does_not_expire_at_once() {
+        let dir = std::env::temp_dir().join(format!("rs-example-self-update-state-{}", std::process::id()));
+        let mut state = UpdateState::load(&dir.join("state.json"));
+        state.mark_bad("9.9.9".to_owned(), Some(1), "", Some(Duration::MAX)).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(state.ignored_versions["9.9.9"].until, Some(u64::MAX));
+        assert!(state.is_bad("9.9.9"));
+    }
+}
@@ -0,0 +1,45 @@
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// --- BANDWIDTH LIMIT ---
+
+/// Writer that sleeps as needed to keep the average rate at or below
+/// `bytes_per_sec`. Slowing down the writes slows down the reads feeding it,
+/// so TCP flow control throttles the download itself.
+pub(crate) struct Throttled<W> {
+    inner: W,
+    bytes_per_sec: u64,
+    started: Instant,
+    written: u64,
+}
+
+impl<W: Write> Throttled<W> {
+    pub(crate) fn new(inner: W, bytes_per_sec: u64) -> Self {
+        Self {
+            inner,
+            bytes_per_sec: bytes_per_sec.max(1),
+            started: Instant::now(),
+            written: 0,
+        }
+    }
+}
+
+impl<W: Write> Write for Throttled<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // At most a tenth of a second's worth at a time, to avoid bursts
+        let chunk = (self.bytes_per_sec / 10).max(1) as usize;
+        let n = self.inner.write(&buf[..buf.len().min(chunk)])?;
+        self.written += n as u64;
+
+        let due = Duration::from_secs_f64(self.written as f64 / self.bytes_per_sec as f64);
+        if let Some(ahead) = due.checked_sub(self.started.elapsed()) {
+            thread::sleep(ahead);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
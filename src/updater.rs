@@ -0,0 +1,838 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::channel::Channel;
+use crate::error::UpdateError;
+use crate::http::NetworkConfig;
+use crate::retry::RetryPolicy;
+use crate::source::{self, GitHub, Release, ReleaseAsset, ReleaseSource};
+use crate::state::{ReleaseCache, UpdateState};
+use crate::throttle::Throttled;
+use crate::{patch, verify};
+
+// --- ENUMS & STRUCTS ---
+
+#[derive(Debug)]
+pub enum UpdateEvent {
+    Message(String),
+    Success(String), // Version
+    UpToDate,
+    Held(String), // Newer version available, but outside the pinned version requirement
+    Staged(String, u8), // Newer version in a staged rollout (percent) not covering this machine yet
+    Retrying(u32, Duration, String), // Next attempt, delay before it, error of the failed one
+    Error(UpdateError),
+}
+
+/// Builder for [`Updater`], see [`Updater::configure`].
+#[derive(Clone, Default)]
+pub struct UpdaterBuilder {
+    source: Option<Arc<dyn ReleaseSource>>,
+    repo_owner: Option<String>,
+    repo_name: Option<String>,
+    bin_name: Option<String>,
+    target: Option<String>,
+    current_version: Option<String>,
+    verifying_keys: Vec<[u8; zipsign_api::PUBLIC_KEY_LENGTH]>,
+    state_path: Option<PathBuf>,
+    health_check_args: Option<Vec<String>>,
+    health_check_timeout: Option<Duration>,
+    channel: Channel,
+    retry: RetryPolicy,
+    download_limit: Option<u64>,
+    check_interval: Duration,
+    retry_failed_after: Option<Duration>,
+    network: NetworkConfig,
+}
+
+impl UpdaterBuilder {
+    /// Where to look for releases. Defaults to [`GitHub`] for `repo_owner`/`repo_name`,
+    /// authenticated with `GITHUB_TOKEN` if set and using [`UpdaterBuilder::network`].
+    pub fn source(&mut self, source: impl ReleaseSource + 'static) -> &mut Self {
+        self.source = Some(Arc::new(source));
+        self
+    }
+
+    pub fn repo_owner(&mut self, owner: &str) -> &mut Self {
+        self.repo_owner = Some(owner.to_owned());
+        self
+    }
+
+    pub fn repo_name(&mut self, name: &str) -> &mut Self {
+        self.repo_name = Some(name.to_owned());
+        self
+    }
+
+    /// Name of the binary inside the release archive. Defaults to the repo name.
+    pub fn bin_name(&mut self, name: &str) -> &mut Self {
+        self.bin_name = Some(name.to_owned());
+        self
+    }
+
+    /// Asset target such as `linux-amd64`. Defaults to [`default_target`].
+    pub fn target(&mut self, target: &str) -> &mut Self {
+        self.target = Some(target.to_owned());
+        self
+    }
+
+    /// Version of the running binary, usually `env!("CARGO_PKG_VERSION")`.
+    pub fn current_version(&mut self, version: &str) -> &mut Self {
+        self.current_version = Some(version.to_owned());
+        self
+    }
+
+    /// zipsign public keys; a release must be signed by one of them.
+    pub fn verifying_keys(
+        &mut self,
+        keys: impl Into<Vec<[u8; zipsign_api::PUBLIC_KEY_LENGTH]>>,
+    ) -> &mut Self {
+        self.verifying_keys = keys.into();
+        self
+    }
+
+    /// Location of `state.json`. Defaults to [`UpdateState::default_path`]
+    /// for the repo owner and binary name.
+    pub fn state_path<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.state_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Arguments passed to the new binary to verify it. Defaults to `--health-check`.
+    pub fn health_check_args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.health_check_args = Some(args.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// How long the health check may run before it is killed and counted as
+    /// failed. Defaults to 30 seconds.
+    pub fn health_check_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.health_check_timeout = Some(timeout);
+        self
+    }
+
+    /// Default release channel. A channel chosen by the user (stored in
+    /// `state.json`) takes precedence.
+    pub fn channel(&mut self, channel: Channel) -> &mut Self {
+        self.channel = channel;
+        self
+    }
+
+    /// Retries for release lookups and downloads. Defaults to [`RetryPolicy::default`].
+    pub fn retry(&mut self, retry: RetryPolicy) -> &mut Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Default bandwidth limit for downloads in bytes per second. A limit set
+    /// on the machine (stored in `state.json`) takes precedence.
+    pub fn download_limit(&mut self, bytes_per_sec: u64) -> &mut Self {
+        self.download_limit = Some(bytes_per_sec);
+        self
+    }
+
+    /// Minimum time between two update checks, e.g. 24 hours for a CLI that is
+    /// started many times a day. Defaults to zero (check on every run);
+    /// `Duration::MAX` turns the automatic checks off.
+    pub fn check_interval(&mut self, interval: Duration) -> &mut Self {
+        self.check_interval = interval;
+        self
+    }
+
+    /// Lets a version that failed its health check be installed again after
+    /// `period`, in case the failure was transient (e.g. a full disk).
+    /// Defaults to never.
+    pub fn retry_failed_after(&mut self, period: Duration) -> &mut Self {
+        self.retry_failed_after = Some(period);
+        self
+    }
+
+    /// Default proxy and CA bundle for the default GitHub source. Settings
+    /// stored on the machine (`state.json`) take precedence. A source passed
+    /// to [`UpdaterBuilder::source`] is configured with its own `with_network`.
+    pub fn network(&mut self, network: NetworkConfig) -> &mut Self {
+        self.network = network;
+        self
+    }
+
+    pub fn build(&self) -> anyhow::Result<Updater> {
+        let bin_name = self
+            .bin_name
+            .clone()
+            .or_else(|| self.repo_name.clone())
+            .ok_or_else(|| anyhow::anyhow!("`bin_name` is required"))?;
+        let state_path = self.state_path.clone().unwrap_or_else(|| {
+            UpdateState::default_path(self.repo_owner.as_deref().unwrap_or(&bin_name), &bin_name)
+        });
+        let source: Arc<dyn ReleaseSource> = match (&self.source, &self.repo_owner, &self.repo_name) {
+            (Some(source), _, _) => source.clone(),
+            (None, Some(owner), Some(name)) => {
+                let network = UpdateState::load(&state_path).network.or(&self.network);
+                Arc::new(GitHub::new(owner, name)?.with_network(&network).with_env_token()?)
+            }
+            _ => anyhow::bail!("either `source` or `repo_owner` and `repo_name` are required"),
+        };
+        let current_version = self
+            .current_version
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("`current_version` is required"))?;
+        if self.verifying_keys.is_empty() {
+            anyhow::bail!("at least one verifying key is required");
+        }
+
+        Ok(Updater {
+            source,
+            bin_name,
+            target: self.target.clone().unwrap_or_else(default_target),
+            current_version,
+            verifying_keys: self.verifying_keys.clone(),
+            state_path,
+            health_check_args: self
+                .health_check_args
+                .clone()
+                .unwrap_or_else(|| vec!["--health-check".to_owned()]),
+            health_check_timeout: self.health_check_timeout.unwrap_or(Duration::from_secs(30)),
+            channel: self.channel,
+            retry: self.retry.clone(),
+            download_limit: self.download_limit,
+            check_interval: self.check_interval,
+            retry_failed_after: self.retry_failed_after,
+        })
+    }
+}
+
+/// Background self-updater: checks a [`ReleaseSource`] for a newer signed
+/// release, installs it, health-checks the new binary and rolls back on failure.
+#[derive(Clone)]
+pub struct Updater {
+    source: Arc<dyn ReleaseSource>,
+    bin_name: String,
+    target: String,
+    current_version: String,
+    verifying_keys: Vec<[u8; zipsign_api::PUBLIC_KEY_LENGTH]>,
+    state_path: PathBuf,
+    health_check_args: Vec<String>,
+    health_check_timeout: Duration,
+    channel: Channel,
+    retry: RetryPolicy,
+    download_limit: Option<u64>,
+    check_interval: Duration,
+    retry_failed_after: Option<Duration>,
+}
+
+/// Asset target for the running platform, following the release naming
+/// convention (`linux-amd64`, `macos-arm64`, ...).
+pub fn default_target() -> String {
+    let os = env::consts::OS;
+    let arch = match env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        _ => env::consts::ARCH,
+    };
+    format!("{}-{}", os, arch)
+}
+
+impl Updater {
+    pub fn configure() -> UpdaterBuilder {
+        UpdaterBuilder::default()
+    }
+
+    pub fn state_path(&self) -> &Path {
+        &self.state_path
+    }
+
+    pub fn load_state(&self) -> UpdateState {
+        UpdateState::load(&self.state_path)
+    }
+
+    /// The user's channel from `state.json`, else the configured default.
+    pub fn channel(&self, state: &UpdateState) -> Channel {
+        state.channel.unwrap_or(self.channel)
+    }
+
+    /// The machine's bandwidth limit from `state.json`, else the configured default.
+    /// Zero means unlimited.
+    pub fn download_limit(&self, state: &UpdateState) -> Option<u64> {
+        state.download_limit.or(self.download_limit).filter(|&limit| limit > 0)
+    }
+
+    /// How long until the next check is due, `None` if it is due now. A due
+    /// time beyond what `SystemTime` can hold (e.g. an interval of
+    /// `Duration::MAX`) is never due.
+    pub fn time_until_next_check(&self, state: &UpdateState) -> Option<Duration> {
+        let due = UNIX_EPOCH
+            .checked_add(Duration::from_secs(state.last_check?))
+            .and_then(|last_check| last_check.checked_add(self.check_interval));
+        match due {
+            Some(due) => due.duration_since(SystemTime::now()).ok().filter(|d| !d.is_zero()),
+            None => Some(Duration::MAX),
+        }
+    }
+
+    /// Runs the update in a background thread and returns the receiving end
+    /// of its event channel. Errors are reported as [`UpdateEvent::Error`].
+    pub fn spawn(self) -> Receiver<UpdateEvent> {
+        let (tx, rx) = channel();
+        thread::spawn(move || {
+            if let Err(e) = self.run(tx.clone()) {
+                let _ = tx.send(UpdateEvent::Error(e));
+            }
+        });
+        rx
+    }
+
+    // --- UPDATE LOGIC (Runs in Background) ---
+
+    /// Checks for and installs an update, unless the last check is more recent
+    /// than the check interval. See [`UpdateError::is_silent`] for the failures
+    /// not meant to be shown to the user.
+    pub fn run(&self, tx: Sender<UpdateEvent>) -> Result<(), UpdateError> {
+        if self.time_until_next_check(&self.load_state()).is_some() {
+            return Ok(());
+        }
+        match self.check(tx.clone())? {
+            Some(release) => Ok(self.apply(&release, self.load_state(), &tx)?),
+            None => Ok(()),
+        }
+    }
+
+    /// Looks for the release [`Updater::run`] would install, without installing
+    /// it. Held back and staged releases are reported as events.
+    pub fn check(&self, tx: Sender<UpdateEvent>) -> Result<Option<Release>, UpdateError> {
+        // No requests until the release API's rate limit resets (silent, spec section 4).
+        // Only the source that hit the limit waits; other sources have their own.
+        let mut state = self.load_state();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let limited = state.rate_limited_source.as_deref() == Some(self.source.name());
+        if let Some(until) = state.rate_limited_until.filter(|&until| limited && now < until) {
+            return Err(UpdateError::RateLimited { until });
+        }
+        let result = self.find_update(&mut state, &tx).map_err(UpdateError::from);
+        if let Err(UpdateError::RateLimited { until }) = result {
+            let mut state = self.load_state();
+            state.rate_limited_until = Some(until);
+            state.rate_limited_source = Some(self.source.name().to_owned());
+            state.save()?;
+        }
+        result
+    }
+
+    fn find_update(&self, state: &mut UpdateState, tx: &Sender<UpdateEvent>) -> anyhow::Result<Option<Release>> {
+        let bucket = state.rollout_bucket()?;
+
+        // 1. Check for Releases (Peek)
+        let channel = self.channel(state);
+        tx.send(UpdateEvent::Message(format!("Querying {} ({} channel)...", self.source.name(), channel)))?;
+        let releases = self.list_releases(state, tx)?;
+        state.last_check = Some(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs());
+        state.save()?;
+        let current = semver::Version::parse(&self.current_version)?;
+        let newer = |v: &semver::Version| channel.accepts(v) && *v > current;
+
+        // Releases whose archive for this platform is not uploaded (yet) cannot be installed
+        let releases: Vec<_> = releases
+            .into_iter()
+            .filter(|r| r.asset_for(&self.target).is_some())
+            .collect();
+        let newest = source::latest_where(releases.clone(), newer);
+
+        // 2. Version Constraint: newer releases outside the pin are held back
+        let req = state.version_req.clone();
+        let allowed = |v: &semver::Version| req.as_ref().is_none_or(|r| r.matches(v));
+        let mut held = false;
+        if let Some(newest) = &newest {
+            if !allowed(&semver::Version::parse(&newest.version)?) {
+                tx.send(UpdateEvent::Held(newest.version.clone()))?;
+                held = true;
+            } else if state.is_bad(&newest.version) {
+                tx.send(UpdateEvent::Message(format!("Skipping bad version {}", newest.version)))?;
+            }
+        }
+
+        // 3. Blacklist Check: fall back to the newest good release
+        let candidate = |v: &semver::Version| newer(v) && allowed(v) && !state.is_bad(&v.to_string());
+        let (releases, staged): (Vec<_>, Vec<_>) = releases.into_iter().partition(|r| r.covers(bucket));
+        let release = source::latest_where(releases, candidate);
+
+        // Staged Rollout: releases not yet offered to this machine's bucket wait
+        if let Some(staged) = source::latest_where(staged, candidate) {
+            let skipped = release.as_ref().is_none_or(|r| {
+                semver::Version::parse(&staged.version).ok() > semver::Version::parse(&r.version).ok()
+            });
+            if skipped {
+                tx.send(UpdateEvent::Staged(staged.version, staged.rollout.unwrap_or_default()))?;
+                held = true;
+            }
+        }
+
+        if release.is_none() && !held {
+            tx.send(UpdateEvent::UpToDate)?;
+        }
+        Ok(release)
+    }
+
+    /// Installs exactly `version`, older ones included (an explicit downgrade),
+    /// through the same backup, signature and health check path as [`Updater::run`].
+    /// Channel, pin, staged rollout and blacklist do not apply; asking the user is up to the caller.
+    /// A downgrade pins `version_req` to `=version` so that the next check does
+    /// not reinstall the newest release; clear the pin to resume updates.
+    pub fn install_version(&self, version: &str, tx: Sender<UpdateEvent>) -> Result<(), UpdateError> {
+        Ok(self.install_exact(version, &tx)?)
+    }
+
+    /// Goes back to the binary the last successful update replaced, and ignores
+    /// the running version so that the next check does not install it again.
+    pub fn rollback(&self) -> Result<(), UpdateError> {
+        Ok(self.restore_backup()?)
+    }
+
+    /// Where the binary replaced by the last update is kept, see [`Updater::rollback`].
+    pub fn backup_path(&self) -> anyhow::Result<PathBuf> {
+        Ok(env::current_exe()?.with_extension("bak"))
+    }
+
+    fn install_exact(&self, version: &str, tx: &Sender<UpdateEvent>) -> anyhow::Result<()> {
+        let version = version.trim_start_matches('v');
+        let mut state = self.load_state();
+
+        tx.send(UpdateEvent::Message(format!("Querying {}...", self.source.name())))?;
+        let release = self
+            .list_releases(&mut state, tx)?
+            .into_iter()
+            .find(|r| r.version == version)
+            .ok_or_else(|| anyhow::anyhow!("Version {} not found on {}", version, self.source.name()))?;
+
+        if state.is_bad(version) {
+            tx.send(UpdateEvent::Message(format!("Installing v{} although it is marked bad", version)))?;
+        }
+        let downgrade = semver::Version::parse(version)? < semver::Version::parse(&self.current_version)?;
+        self.apply(&release, state, tx)?;
+
+        // Keep the older version until the user unpins it
+        if downgrade {
+            let mut state = self.load_state();
+            let pin = semver::VersionReq::parse(&format!("={}", version))?;
+            tx.send(UpdateEvent::Message(format!("Pinned to {} to stay on this version", pin)))?;
+            state.version_req = Some(pin);
+            state.save()?;
+        }
+        Ok(())
+    }
+
+    fn restore_backup(&self) -> anyhow::Result<()> {
+        let backup_path = self.backup_path()?;
+        if !backup_path.exists() {
+            anyhow::bail!("No previous version to roll back to");
+        }
+        self_update::self_replace::self_replace(&backup_path)?;
+        fs::remove_file(&backup_path)?;
+        self.load_state()
+            .ignore(self.current_version.clone(), Some("Rolled back".to_owned()))
+    }
+
+    /// Lists releases with a conditional request, reusing the listing cached
+    /// in `state` if the source reports it unchanged.
+    fn list_releases(&self, state: &mut UpdateState, tx: &Sender<UpdateEvent>) -> anyhow::Result<Vec<Release>> {
+        let etag = state.release_cache.as_ref().map(|cache| cache.etag.clone());
+        let listing = self.retry.run(
+            || self.source.list_releases_if_changed(etag.as_deref()),
+            |attempt, delay, e| retried(tx, attempt, delay, e),
+        )?;
+        match (listing, &state.release_cache) {
+            (None, Some(cache)) => Ok(cache.releases.clone()),
+            (None, None) => self.source.list_releases(), // Not asked, but be safe
+            (Some((releases, etag)), _) => {
+                let cache = etag.map(|etag| ReleaseCache {
+                    etag,
+                    releases: releases.clone(),
+                });
+                if cache.is_some() || state.release_cache.is_some() {
+                    state.release_cache = cache;
+                    state.save()?;
+                }
+                Ok(releases)
+            }
+        }
+    }
+
+    // Backup, install, health check and rollback
+    fn apply(&self, release: &Release, mut state: UpdateState, tx: &Sender<UpdateEvent>) -> anyhow::Result<()> {
+        let current_exe = env::current_exe()?;
+        let backup_path = self.backup_path()?;
+        // The rollback target of the running version, until the new one is healthy
+        let previous_backup = current_exe.with_extension("bak.old");
+
+        // 4. Update Sequence
+        match self.source.served_by() {
+            Some(mirror) => tx.send(UpdateEvent::Message(format!("Downloading v{} from {}...", release.version, mirror)))?,
+            None => tx.send(UpdateEvent::Message(format!("Downloading v{}...", release.version)))?,
+        }
+
+        // Create Backup, setting the old one aside
+        if backup_path.exists() {
+            fs::rename(&backup_path, &previous_backup)?;
+        }
+        let restore_previous = || {
+            if previous_backup.exists() {
+                let _ = fs::rename(&previous_backup, &backup_path);
+            }
+        };
+        if let Err(e) = fs::copy(&current_exe, &backup_path) {
+            restore_previous();
+            return Err(e.into());
+        }
+
+        // Perform Update (Swap binary on disk)
+        // Note: On Windows, self_replace renames the running file to allow writing the new one.
+        // The running process continues in memory fine.
+        if let Err(e) = self.install(release, tx) {
+            // Network/Signature error - restore backup just in case
+            let _ = fs::rename(&backup_path, &current_exe);
+            restore_previous();
+            return Err(e);
+        }
+
+        // Remember which mirror the new binary came from
+        if let Some(mirror) = self.source.served_by() {
+            state.last_mirror = Some(mirror);
+            state.save()?;
+        }
+
+        let new_version = release.version.clone();
+        tx.send(UpdateEvent::Message("Verifying new binary health...".into()))?;
+
+        // 5. Health Check
+        let output = health_check(&current_exe, &self.health_check_args, self.health_check_timeout);
+
+        match &output {
+            Ok((status, _)) if status.success() => {
+                // Success! The backup stays for `rollback`
+                let _ = fs::remove_file(&previous_backup);
+                tx.send(UpdateEvent::Success(new_version))?;
+            }
+            _ => {
+                // Fail! Rollback
+                tx.send(UpdateEvent::Message("Health check failed. Rolling back...".into()))?;
+
+                // Mark bad, with what the health check said
+                let (exit_code, stderr) = match &output {
+                    Ok((status, stderr)) => (status.code(), String::from_utf8_lossy(stderr).into_owned()),
+                    Err(e) => (None, e.to_string()),
+                };
+                state.mark_bad(new_version.clone(), exit_code, &stderr, self.retry_failed_after)?;
+
+                // Restore backup
+                // On Windows, we overwrite the "new" broken file with the backup
+                fs::rename(&backup_path, &current_exe)?;
+                restore_previous();
+                return Err(UpdateError::HealthCheck { version: new_version }.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Downloads the release for this target, preferring a delta patch from
+    /// the running version, verifies its signature and swaps it in place of
+    /// the running executable.
+    fn install(&self, release: &Release, tx: &Sender<UpdateEvent>) -> anyhow::Result<()> {
+        let tmp_dir = self_update::TempDir::new()?;
+        let bin = format!("{}{}", self.bin_name, env::consts::EXE_SUFFIX);
+
+        if let Some(patch) = release.patch_for(&self.target, &self.current_version) {
+            match self.patched_binary(release, patch, &bin, tmp_dir.path(), tx) {
+                Ok(new_bin) => {
+                    self_update::self_replace::self_replace(new_bin)?;
+                    let _ = fs::remove_dir_all(self.download_dir());
+                    return Ok(());
+                }
+                // A bad signature is an attack, not a reason to try the full archive
+                Err(e) if matches!(e.downcast_ref(), Some(UpdateError::Signature(_))) => return Err(e),
+                Err(e) => tx.send(UpdateEvent::Message(format!(
+                    "Patch failed ({:#}), downloading full archive...",
+                    e
+                )))?,
+            }
+        }
+
+        let asset = release
+            .asset_for(&self.target)
+            .ok_or_else(|| anyhow::anyhow!("No asset found for target: `{}`", self.target))?;
+        let archive_path = self.download_verified(release, asset, tx)?;
+        self_update::Extract::from_source(&archive_path).extract_file(tmp_dir.path(), &bin)?;
+        self_update::self_replace::self_replace(tmp_dir.path().join(&bin))?;
+        let _ = fs::remove_dir_all(self.download_dir());
+        Ok(())
+    }
+
+    /// Applies a signed patch archive (`<bin>.zst` plus the `<bin>.sha256` of
+    /// the result) to the running binary. Returns the path of the new binary.
+    fn patched_binary(
+        &self,
+        release: &Release,
+        asset: &ReleaseAsset,
+        bin: &str,
+        dir: &Path,
+        tx: &Sender<UpdateEvent>,
+    ) -> anyhow::Result<PathBuf> {
+        let archive_path = self.download_verified(release, asset, tx)?;
+        let patch_dir = dir.join("patch");
+        fs::create_dir_all(&patch_dir)?;
+        let zst = format!("{}.zst", bin);
+        let sha256 = format!("{}.sha256", bin);
+        self_update::Extract::from_source(&archive_path).extract_file(&patch_dir, &zst)?;
+        self_update::Extract::from_source(&archive_path).extract_file(&patch_dir, &sha256)?;
+
+        let new_bin = patch_dir.join(bin);
+        patch::apply(&env::current_exe()?, &patch_dir.join(&zst), &new_bin)?;
+
+        // `sha256sum` format; a mismatch means we are not running the binary the patch was made from
+        let expected = fs::read_to_string(patch_dir.join(&sha256))?;
+        let expected = expected.split_whitespace().next().unwrap_or_default();
+        verify::verify_digest(&new_bin, None, Some(expected))?;
+        Ok(new_bin)
+    }
+
+    /// Downloads `asset` into the cache directory and checks its size, hash
+    /// and signature. An interrupted download is kept as `<name>.part` and
+    /// resumed on the next attempt.
+    fn download_verified(
+        &self,
+        release: &Release,
+        asset: &ReleaseAsset,
+        tx: &Sender<UpdateEvent>,
+    ) -> anyhow::Result<PathBuf> {
+        let dir = self.download_dir().join(format!("v{}", release.version));
+        fs::create_dir_all(&dir)?;
+        let archive_path = dir.join(&asset.name); // The name is the signing context
+        let part_path = dir.join(format!("{}.part", asset.name));
+        let limit = self.download_limit(&self.load_state());
+
+        // A retry picks up where the failed attempt stopped
+        let download = || -> anyhow::Result<()> {
+            let offset = fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+            let file = fs::OpenOptions::new().create(true).append(true).open(&part_path)?;
+            if asset.size.is_some_and(|size| offset >= size) {
+                return Ok(());
+            }
+            let mut part: Box<dyn Write> = match limit {
+                Some(limit) => Box::new(Throttled::new(&file, limit)),
+                None => Box::new(&file),
+            };
+            let resumed = offset > 0 && {
+                tx.send(UpdateEvent::Message(format!("Resuming {} at {} bytes...", asset.name, offset)))?;
+                self.source.resume_asset(asset, offset, &mut part)?
+            };
+            if !resumed {
+                file.set_len(0)?;
+                self.source.download_asset(asset, &mut part)?;
+            }
+            part.flush()?;
+            Ok(())
+        };
+        self.retry
+            .run(download, |attempt, delay, e| retried(tx, attempt, delay, e))
+            .map_err(UpdateError::download)?;
+
+        // Catch truncated or corrupted downloads early if the backend publishes size/hash
+        if let Err(e) = verify::verify_digest(&part_path, asset.size, asset.sha256.as_deref()) {
+            let _ = fs::remove_file(&part_path);
+            return Err(UpdateError::Download(e.into()).into());
+        }
+        fs::rename(&part_path, &archive_path)?;
+
+        // Nothing is touched unless the signature matches one of our keys;
+        // everything downloaded for this version goes (spec section 4)
+        if let Err(e) = verify::verify_archive(&archive_path, &self.verifying_keys) {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(UpdateError::Signature(e.into()).into());
+        }
+        Ok(archive_path)
+    }
+
+    // Partial downloads live next to `state.json`, i.e. in the cache directory
+    fn download_dir(&self) -> PathBuf {
+        self.state_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("downloads")
+    }
+}
+
+fn retried(tx: &Sender<UpdateEvent>, attempt: u32, delay: Duration, error: &anyhow::Error) -> anyhow::Result<()> {
+    tx.send(UpdateEvent::Retrying(attempt, delay, format!("{:#}", error)))?;
+    Ok(())
+}
+
+/// Runs the new binary with `args`, killing it after `timeout`. Returns its
+/// exit status and stderr.
+fn health_check(exe: &Path, args: &[String], timeout: Duration) -> io::Result<(ExitStatus, Vec<u8>)> {
+    let mut child = Command::new(exe)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Read stderr on the side so a chatty binary cannot block on a full pipe
+    let mut pipe = child.stderr.take().expect("stderr is piped");
+    let reader = thread::spawn(move || {
+        let mut stderr = Vec::new();
+        let _ = pipe.read_to_end(&mut stderr);
+        stderr
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("Health check timed out after {}s", timeout.as_secs()),
+            ));
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    Ok((status, reader.join().unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    struct Fixed(Vec<Release>);
+
+    impl ReleaseSource for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        fn list_releases(&self) -> anyhow::Result<Vec<Release>> {
+            Ok(self.0.clone())
+        }
+
+        fn download_asset(&self, _asset: &ReleaseAsset, _dest: &mut dyn Write) -> anyhow::Result<()> {
+            anyhow::bail!("not downloadable")
+        }
+    }
+
+    fn release(version: &str, rollout: Option<u8>) -> Release {
+        Release {
+            version: version.to_owned(),
+            assets: vec![ReleaseAsset {
+                name: format!("app-linux-amd64-{}.tar.gz", version),
+                ..Default::default()
+            }],
+            rollout,
+        }
+    }
+
+    // Updater for v1.0.0 on linux-amd64 with its own `state.json`
+    fn updater(name: &str, releases: Vec<Release>) -> (UpdaterBuilder, PathBuf) {
+        let state_path = env::temp_dir()
+            .join(format!("rs-example-self-update-test-{}", process::id()))
+            .join(name)
+            .join("state.json");
+        let mut builder = Updater::configure();
+        builder
+            .source(Fixed(releases))
+            .bin_name("app")
+            .target("linux-amd64")
+            .current_version("1.0.0")
+            .verifying_keys(vec![[0; zipsign_api::PUBLIC_KEY_LENGTH]])
+            .state_path(&state_path);
+        (builder, state_path)
+    }
+
+    // Version `find_update` picks from `releases` for a machine in rollout bucket 50
+    fn pick(name: &str, releases: Vec<Release>, edit: impl FnOnce(&mut UpdateState)) -> Option<String> {
+        let (builder, state_path) = updater(name, releases);
+        let updater = builder.build().unwrap();
+        let mut state = UpdateState::load(&state_path);
+        state.rollout_bucket = Some(50);
+        edit(&mut state);
+        let (tx, _rx) = channel();
+        let picked = updater.find_update(&mut state, &tx).unwrap();
+        let _ = fs::remove_dir_all(state_path.parent().unwrap());
+        picked.map(|r| r.version)
+    }
+
+    #[test]
+    fn picks_newest_newer_release() {
+        let releases = vec![release("0.9.0", None), release("1.1.0", None), release("1.2.0", None)];
+        assert_eq!(pick("newest", releases, |_| {}).as_deref(), Some("1.2.0"));
+        assert_eq!(pick("older", vec![release("0.9.0", None)], |_| {}), None);
+    }
+
+    #[test]
+    fn channel_decides_on_pre_releases() {
+        let releases = vec![release("1.1.0", None), release("1.2.0-beta.1", None)];
+        assert_eq!(pick("stable", releases.clone(), |_| {}).as_deref(), Some("1.1.0"));
+        let beta = pick("beta", releases, |state| state.channel = Some(Channel::Beta));
+        assert_eq!(beta.as_deref(), Some("1.2.0-beta.1"));
+    }
+
+    #[test]
+    fn pin_holds_back_newer_releases() {
+        let releases = vec![release("1.1.0", None), release("2.0.0", None)];
+        let picked = pick("pin", releases, |state| state.version_req = Some("<2".parse().unwrap()));
+        assert_eq!(picked.as_deref(), Some("1.1.0"));
+    }
+
+    #[test]
+    fn falls_back_from_bad_release() {
+        let releases = vec![release("1.1.0", None), release("1.2.0", None)];
+        let picked = pick("bad", releases, |state| state.ignore("1.2.0".to_owned(), None).unwrap());
+        assert_eq!(picked.as_deref(), Some("1.1.0"));
+    }
+
+    #[test]
+    fn waits_for_staged_rollout() {
+        let releases = vec![release("1.1.0", Some(100)), release("1.2.0", Some(10))];
+        assert_eq!(pick("staged", releases, |_| {}).as_deref(), Some("1.1.0"));
+        let covered = vec![release("1.2.0", Some(60))];
+        assert_eq!(pick("covered", covered, |_| {}).as_deref(), Some("1.2.0"));
+    }
+
+    #[test]
+    fn skips_release_without_asset_for_target() {
+        let mut uploading = release("1.2.0", None);
+        uploading.assets[0].name = "app-macos-arm64-1.2.0.tar.gz".to_owned();
+        let empty = Release {
+            version: "1.3.0".to_owned(),
+            ..Default::default()
+        };
+        let releases = vec![release("1.1.0", None), uploading, empty];
+        assert_eq!(pick("assets", releases, |_| {}).as_deref(), Some("1.1.0"));
+    }
+
+    #[test]
+    fn next_check_survives_overflow() {
+        let (mut builder, state_path) = updater("interval", Vec::new());
+        let mut state = UpdateState::load(&state_path);
+        state.last_check = Some(u64::MAX);
+        assert_eq!(builder.build().unwrap().time_until_next_check(&state), Some(Duration::MAX));
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        state.last_check = Some(now);
+        let never = builder.check_interval(Duration::MAX).build().unwrap();
+        assert_eq!(never.time_until_next_check(&state), Some(Duration::MAX));
+        let always = builder.check_interval(Duration::ZERO).build().unwrap();
+        assert_eq!(always.time_until_next_check(&state), None);
+    }
+}
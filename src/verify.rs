@@ -0,0 +1,62 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+use zipsign_api::PUBLIC_KEY_LENGTH;
+
+// --- SIGNATURE VERIFICATION (zipsign) ---
+
+/// Verifies the signature embedded in a `.tar.gz` or `.zip` archive. The
+/// archive's file name is the signing context, as with `zipsign sign`.
+pub(crate) fn verify_archive(path: &Path, keys: &[[u8; PUBLIC_KEY_LENGTH]]) -> anyhow::Result<()> {
+    let name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Cannot verify signature of a file with a non-UTF-8 name"))?;
+    let keys = zipsign_api::verify::collect_keys(keys.iter().copied().map(Ok))?;
+    let mut file = fs::File::open(path)?;
+
+    if name.ends_with(".zip") {
+        zipsign_api::verify::verify_zip(&mut file, &keys, Some(name.as_bytes()))?;
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        zipsign_api::verify::verify_tar(&mut file, &keys, Some(name.as_bytes()))?;
+    } else {
+        anyhow::bail!("No signature verification implemented for {}", name);
+    }
+    Ok(())
+}
+
+/// Verifies a detached zipsign signature block (`<file>.sig`) over `data`,
+/// using `context` (normally the file name) as the signing context.
+pub(crate) fn verify_detached(
+    data: &[u8],
+    signature: &[u8],
+    context: &str,
+    keys: &[[u8; PUBLIC_KEY_LENGTH]],
+) -> anyhow::Result<()> {
+    let keys = zipsign_api::verify::collect_keys(keys.iter().copied().map(Ok))?;
+    let signatures = zipsign_api::verify::read_signatures(&mut &signature[..])?;
+    let prehash = zipsign_api::Prehash::calculate(&mut &data[..])?;
+    zipsign_api::verify::find_match(&keys, &signatures, &prehash, Some(context.as_bytes()))?;
+    Ok(())
+}
+
+/// Checks a downloaded file against the size and SHA-256 published for it.
+pub(crate) fn verify_digest(path: &Path, size: Option<u64>, sha256: Option<&str>) -> anyhow::Result<()> {
+    if let Some(size) = size {
+        let actual = fs::metadata(path)?.len();
+        if actual != size {
+            anyhow::bail!("Size mismatch: expected {} bytes, got {}", size, actual);
+        }
+    }
+    if let Some(expected) = sha256 {
+        let mut hasher = Sha256::new();
+        io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+        let actual = hex::encode(hasher.finalize());
+        if !actual.eq_ignore_ascii_case(expected) {
+            anyhow::bail!("SHA-256 mismatch: expected {}, got {}", expected, actual);
+        }
+    }
+    Ok(())
+}